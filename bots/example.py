@@ -0,0 +1,50 @@
+#!/usr/bin/env python3
+"""An external bot: goes straight until something is in the way, then turns to
+whichever side is free. See src/external.rs for the protocol."""
+
+import json
+import sys
+
+STEPS = {"Left": (-1, 0), "Right": (1, 0), "Up": (0, 1), "Down": (0, -1)}
+LEFT_OF = {"Up": "Left", "Left": "Down", "Down": "Right", "Right": "Up"}
+RIGHT_OF = {value: key for key, value in LEFT_OF.items()}
+
+covered = set()
+# The heading asked for in the last answer, which is taken before this one
+answered = None
+
+for line in sys.stdin:
+    state = json.loads(line)
+    if state["reset"]:
+        covered.clear()
+        answered = None
+    for x, y, _owner in state["filled"]:
+        covered.add((x, y))
+    for x, y in state["freed"]:
+        covered.discard((x, y))
+    me = next(head for head in state["heads"] if head["slot"] == state["you"])
+
+    def step(position, direction):
+        dx, dy = STEPS[direction]
+        x, y = position[0] + dx, position[1] + dy
+        if state["wrap_around"]:
+            x, y = x % state["width"], y % state["height"]
+        return x, y
+
+    # This answer is only taken on the next tick, once the last one has moved the
+    # snake one cell further
+    heading = answered or me["direction"]
+    ahead = step((me["position"]["x"], me["position"]["y"]), heading)
+
+    def free(direction):
+        x, y = step(ahead, direction)
+        inside = 0 <= x < state["width"] and 0 <= y < state["height"]
+        return inside and (x, y) not in covered
+
+    if free(heading):
+        answered = heading
+    elif free(LEFT_OF[heading]):
+        answered = LEFT_OF[heading]
+    else:
+        answered = RIGHT_OF[heading]
+    print(json.dumps({"face": answered}), flush=True)
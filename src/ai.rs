@@ -0,0 +1,293 @@
+//! Computer-controlled players. They look at the simulation the way a human looks at
+//! the screen and answer with the same turns a human would press.
+
+use rand::Rng;
+use std::collections::VecDeque;
+
+use crate::sim;
+
+/// Cells a flood fill counts at most; more room than that is as good as unlimited
+const FLOOD_LIMIT: usize = 600;
+/// Moves ahead the hard AI tries every combination of
+const SEARCH_DEPTH: usize = 3;
+/// Chance per tick that the easy AI turns although going straight is safe
+const WANDER: f64 = 0.03;
+
+/// Going straight first, so ties keep the current heading
+const MOVES: [Option<sim::Turn>; 3] = [None, Some(sim::Turn::Left), Some(sim::Turn::Right)];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Difficulty {
+  /// Picks a random move that doesn't crash right away
+  Easy,
+  /// Moves towards the most open space
+  Medium,
+  /// Searches a few moves ahead for the path that keeps the most open space
+  Hard,
+}
+
+impl Difficulty {
+  pub fn label(self) -> &'static str {
+    match self {
+      Difficulty::Easy => "AI easy",
+      Difficulty::Medium => "AI medium",
+      Difficulty::Hard => "AI hard",
+    }
+  }
+
+  /// The turn the snake in `slot` takes this tick, `None` to keep going straight.
+  /// `scratch` is best kept for the snake's next decisions.
+  pub fn turn(
+    self,
+    simulation: &sim::Simulation,
+    slot: usize,
+    scratch: &mut Scratch,
+    rng: &mut impl Rng,
+  ) -> Option<sim::Turn> {
+    let snake = simulation.snake(slot)?;
+    let mut grid = Grid::new(simulation, slot, scratch);
+    let moves: Vec<(Option<sim::Turn>, sim::Direction, sim::Position)> = MOVES
+      .into_iter()
+      .map(|turn| {
+        let direction = turn.map_or(snake.direction, |turn| snake.direction.apply(turn));
+        (turn, direction, simulation.destination(snake, direction))
+      })
+      .filter(|(_, _, next)| grid.is_open(*next))
+      .collect();
+    // Cells other heads may move into are only risked when nothing else is left
+    let careful: Vec<_> = moves
+      .iter()
+      .copied()
+      .filter(|(_, _, next)| !grid.contested.contains(next))
+      .collect();
+    let moves = if careful.is_empty() { moves } else { careful };
+
+    match self {
+      Difficulty::Easy => {
+        if moves.first().is_some_and(|(turn, _, _)| turn.is_none()) && !rng.gen_bool(WANDER) {
+          return None;
+        }
+        if moves.is_empty() {
+          return None;
+        }
+        moves[rng.gen_range(0..moves.len())].0
+      }
+      Difficulty::Medium => best(&moves, |_, next| grid.flood(next, &[next])),
+      Difficulty::Hard => best(&moves, |direction, next| {
+        let mut path = vec![next];
+        grid.search(next, direction, SEARCH_DEPTH - 1, &mut path)
+      }),
+    }
+  }
+}
+
+/// The move with the highest value, the earliest one on ties
+fn best(
+  moves: &[(Option<sim::Turn>, sim::Direction, sim::Position)],
+  mut value: impl FnMut(sim::Direction, sim::Position) -> usize,
+) -> Option<sim::Turn> {
+  let mut best: Option<(Option<sim::Turn>, usize)> = None;
+  for &(turn, direction, next) in moves {
+    let value = value(direction, next);
+    if best.is_none_or(|(_, best)| value > best) {
+      best = Some((turn, value));
+    }
+  }
+  best.and_then(|(turn, _)| turn)
+}
+
+/// Memory the flood fills share, so a decision doesn't allocate a counter for every
+/// cell of the arena
+pub struct Scratch {
+  /// Number of the flood fill that last reached each cell, so the cells don't need
+  /// clearing between fills
+  seen: Vec<u32>,
+  floods: u32,
+}
+
+impl Default for Scratch {
+  fn default() -> Self {
+    Scratch {
+      seen: vec![0; (sim::ARENA_WIDTH * sim::ARENA_HEIGHT) as usize],
+      floods: 0,
+    }
+  }
+}
+
+impl Scratch {
+  /// Number for a new flood fill, different from every one still in `seen`
+  fn next_flood(&mut self) -> u32 {
+    if self.floods == u32::MAX {
+      self.seen.fill(0);
+      self.floods = 0;
+    }
+    self.floods += 1;
+    self.floods
+  }
+}
+
+/// The arena as one snake sees it
+struct Grid<'a> {
+  simulation: &'a sim::Simulation,
+  wraps: bool,
+  /// Cells the other snakes can reach with their next move
+  contested: Vec<sim::Position>,
+  scratch: &'a mut Scratch,
+}
+
+impl<'a> Grid<'a> {
+  fn new(simulation: &'a sim::Simulation, slot: usize, scratch: &'a mut Scratch) -> Self {
+    let snake = simulation.snake(slot).unwrap();
+    let contested = simulation
+      .alive()
+      .filter(|other| *other != slot)
+      .filter_map(|other| simulation.snake(other))
+      .flat_map(|other| {
+        MOVES.into_iter().map(move |turn| {
+          let direction = turn.map_or(other.direction, |turn| other.direction.apply(turn));
+          simulation.destination(other, direction)
+        })
+      })
+      .collect();
+    Grid {
+      simulation,
+      wraps: simulation.wrap_around() || snake.has(sim::PowerUp::Wrap),
+      contested,
+      scratch,
+    }
+  }
+
+  fn is_open(&self, position: sim::Position) -> bool {
+    position.in_arena() && self.simulation.occupancy().is_free(position)
+  }
+
+  fn neighbour(&self, position: sim::Position, direction: sim::Direction) -> sim::Position {
+    let next = position.step(direction);
+    if self.wraps {
+      next.wrapped()
+    } else {
+      next
+    }
+  }
+
+  /// Open cells reachable from `start` without crossing `blocked`, up to `FLOOD_LIMIT`
+  fn flood(&mut self, start: sim::Position, blocked: &[sim::Position]) -> usize {
+    let flood = self.scratch.next_flood();
+    let index =
+      |position: sim::Position| (position.y as u32 * sim::ARENA_WIDTH + position.x as u32) as usize;
+    // Marking the cells to avoid as reached already keeps them out of the fill
+    for position in blocked.iter().chain(&self.contested).chain([&start]) {
+      if position.in_arena() {
+        self.scratch.seen[index(*position)] = flood;
+      }
+    }
+    let mut count = 1;
+    let mut queue = VecDeque::from([start]);
+    while let Some(position) = queue.pop_front() {
+      if count >= FLOOD_LIMIT {
+        break;
+      }
+      for direction in [
+        sim::Direction::Left,
+        sim::Direction::Right,
+        sim::Direction::Up,
+        sim::Direction::Down,
+      ] {
+        let next = self.neighbour(position, direction);
+        if self.is_open(next) && self.scratch.seen[index(next)] != flood {
+          self.scratch.seen[index(next)] = flood;
+          count += 1;
+          queue.push_back(next);
+        }
+      }
+    }
+    count
+  }
+
+  /// Most open space left after `depth` more moves from `head`, not crossing `path`
+  fn search(
+    &mut self,
+    head: sim::Position,
+    direction: sim::Direction,
+    depth: usize,
+    path: &mut Vec<sim::Position>,
+  ) -> usize {
+    if depth == 0 {
+      return self.flood(head, path);
+    }
+    let mut best = 0;
+    for turn in MOVES {
+      let direction = turn.map_or(direction, |turn| direction.apply(turn));
+      let next = self.neighbour(head, direction);
+      if !self.is_open(next) || path.contains(&next) {
+        continue;
+      }
+      path.push(next);
+      best = best.max(self.search(next, direction, depth - 1, path));
+      path.pop();
+    }
+    best
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rand::{rngs::StdRng, SeedableRng};
+
+  #[test]
+  fn turns_away_from_the_wall() {
+    let mut simulation = sim::Simulation::new(1, 0);
+    let wall = sim::ARENA_WIDTH as i32 - 1;
+    simulation.spawn_at(0, sim::Position { x: wall, y: 20 }, sim::Direction::Right);
+    let mut scratch = Scratch::default();
+    let mut rng = StdRng::seed_from_u64(0);
+    for difficulty in [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard] {
+      let turn = difficulty.turn(&simulation, 0, &mut scratch, &mut rng);
+      assert!(turn.is_some(), "{:?} drove into the wall", difficulty);
+    }
+  }
+
+  #[test]
+  fn stays_out_of_a_dead_end() {
+    // Turning left leads into a closed pocket and straight ahead is blocked
+    let block = |x, y, width, height| sim::Obstacle {
+      x,
+      y,
+      width,
+      height,
+    };
+    let mut simulation = sim::Simulation::new(1, 0);
+    simulation.set_level(sim::Level {
+      obstacles: vec![
+        block(94, 101, 6, 1),
+        block(101, 101, 6, 1),
+        block(94, 102, 1, 9),
+        block(106, 102, 1, 9),
+        block(94, 111, 13, 1),
+        block(101, 100, 1, 1),
+      ],
+      ..sim::Level::default()
+    });
+    simulation.spawn_at(0, sim::Position { x: 99, y: 100 }, sim::Direction::Right);
+    simulation.step(&[]);
+    let mut scratch = Scratch::default();
+    let mut rng = StdRng::seed_from_u64(0);
+    for difficulty in [Difficulty::Medium, Difficulty::Hard, Difficulty::Medium] {
+      // Later decisions reuse the flood counters of earlier ones
+      let turn = difficulty.turn(&simulation, 0, &mut scratch, &mut rng);
+      assert_eq!(turn, Some(sim::Turn::Right), "{:?}", difficulty);
+    }
+  }
+
+  #[test]
+  fn flood_numbers_start_over_before_running_out() {
+    let mut scratch = Scratch {
+      floods: u32::MAX,
+      ..Scratch::default()
+    };
+    scratch.seen[7] = u32::MAX;
+    assert_eq!(scratch.next_flood(), 1);
+    assert_eq!(scratch.seen[7], 0);
+  }
+}
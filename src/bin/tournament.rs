@@ -0,0 +1,184 @@
+//! Plays bots against each other without a window and prints the standings.
+//!
+//! Usage: tournament [--rounds N] [--seed S] [BOT...]
+//!
+//! Every named bot gets a slot; the same bot may be named more than once. Without
+//! names every registered bot plays once, the external ones in `bots/bots.ron`
+//! included.
+
+use furious_purpose::{bot, external, scoring, sim};
+
+/// A round still going after this many ticks ends with its survivors drawing
+const MAX_TICKS: u64 = 20_000;
+
+/// How one slot has done over the tournament
+#[derive(Default)]
+struct Standing {
+  wins: usize,
+  /// Opponents outlived, scored like `scoring::ScoringScheme::Outlived`
+  points: i32,
+  ticks_alive: u64,
+}
+
+struct Options {
+  rounds: usize,
+  seed: u64,
+  bots: Vec<String>,
+}
+
+fn parse_options(registry: &bot::BotRegistry) -> Result<Options, String> {
+  let mut options = Options {
+    rounds: 100,
+    seed: 0,
+    bots: Vec::new(),
+  };
+  let mut args = std::env::args().skip(1);
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "--rounds" | "--seed" => {
+        let value = args.next().ok_or(format!("{} needs a value", arg))?;
+        let number: u64 = value
+          .parse()
+          .map_err(|_| format!("{} is not a number: {}", arg, value))?;
+        match arg.as_str() {
+          "--rounds" => options.rounds = number as usize,
+          _ => options.seed = number,
+        }
+      }
+      name if registry.names().any(|known| known == name) => options.bots.push(arg),
+      _ => {
+        let known: Vec<&str> = registry.names().collect();
+        return Err(format!("Unknown bot {:?}, pick from {:?}", arg, known));
+      }
+    }
+  }
+  if options.bots.is_empty() {
+    options.bots = registry.names().map(str::to_string).collect();
+  }
+  if options.bots.len() < 2 {
+    return Err("A tournament needs at least two bots".to_string());
+  }
+  Ok(options)
+}
+
+/// Plays one round, adds its outcome to `standings` and returns the winner, `None`
+/// for a draw
+fn play_round(
+  bots: &mut [Box<dyn bot::Bot>],
+  seed: u64,
+  standings: &mut [Standing],
+) -> Option<usize> {
+  let scheme = scoring::ScoringScheme::Outlived;
+  let slots: Vec<usize> = (0..bots.len()).collect();
+  for bot in bots.iter_mut() {
+    bot.new_round();
+  }
+  let mut simulation = sim::Simulation::new(bots.len(), seed);
+  simulation.spawn_all(&slots);
+  let mut ticks = 0;
+  while simulation.alive().count() > 1 && ticks < MAX_TICKS {
+    let mut turns = vec![None; slots.len()];
+    // Every elimination with the slots still alive right after it, who outlived it
+    let mut eliminations: Vec<(sim::Elimination, Vec<usize>)> = Vec::new();
+    for &slot in &slots {
+      if !simulation.is_alive(slot) {
+        continue;
+      }
+      bots[slot].wait();
+      match bots[slot].decide(&bot::ArenaView::new(&simulation, slot)) {
+        Ok(turn) => turns[slot] = turn,
+        Err(bot::Disqualified(reason)) => {
+          eprintln!("Slot {} is disqualified: {}", slot + 1, reason);
+          if let Some(elimination) = simulation.disqualify(slot) {
+            eliminations.push((elimination, simulation.alive().collect()));
+          }
+        }
+      }
+    }
+    let stepped = simulation.step(&turns);
+    let alive: Vec<usize> = simulation.alive().collect();
+    eliminations.extend(
+      stepped
+        .into_iter()
+        .map(|elimination| (elimination, alive.clone())),
+    );
+    ticks += 1;
+    for (elimination, alive) in eliminations {
+      standings[elimination.slot].ticks_alive += ticks;
+      for (slot, points) in scheme.eliminated(elimination, &alive, ticks) {
+        standings[slot].points += points;
+      }
+    }
+  }
+  let survivors: Vec<usize> = simulation.alive().collect();
+  for slot in &survivors {
+    standings[*slot].ticks_alive += ticks;
+  }
+  match survivors[..] {
+    [winner] => {
+      standings[winner].wins += 1;
+      Some(winner)
+    }
+    _ => None,
+  }
+}
+
+fn main() {
+  let mut registry = bot::BotRegistry::builtin();
+  match external::load_config(external::CONFIG_FILE) {
+    Ok(configs) => configs
+      .into_iter()
+      .for_each(|config| config.register(&mut registry)),
+    Err(error) => eprintln!("Ignoring external bots: {}", error),
+  }
+  let options = match parse_options(&registry) {
+    Ok(options) => options,
+    Err(error) => {
+      eprintln!("{}", error);
+      eprintln!("Usage: tournament [--rounds N] [--seed S] [BOT...]");
+      std::process::exit(2);
+    }
+  };
+  let mut bots: Vec<Box<dyn bot::Bot>> = options
+    .bots
+    .iter()
+    .enumerate()
+    .map(|(slot, name)| {
+      registry
+        .create(name, options.seed.wrapping_add(slot as u64))
+        .unwrap()
+    })
+    .collect();
+  let mut standings: Vec<Standing> = options.bots.iter().map(|_| Standing::default()).collect();
+  let mut draws = 0;
+  for round in 0..options.rounds {
+    let seed = options
+      .seed
+      .wrapping_mul(1_000_003)
+      .wrapping_add(round as u64);
+    if play_round(&mut bots, seed, &mut standings).is_none() {
+      draws += 1;
+    }
+  }
+
+  let mut order: Vec<usize> = (0..standings.len()).collect();
+  order.sort_by_key(|&slot| {
+    let standing = &standings[slot];
+    std::cmp::Reverse((standing.wins, standing.points))
+  });
+  println!(
+    "{} rounds, {} drawn\n\n{:<4} {:<20} {:>6} {:>8} {:>12}",
+    options.rounds, draws, "#", "Bot", "Wins", "Outlived", "Avg. ticks"
+  );
+  for (rank, slot) in order.into_iter().enumerate() {
+    let standing = &standings[slot];
+    println!(
+      "{:<4} {:<20} {:>6} {:>8} {:>12}",
+      rank + 1,
+      format!("{} (slot {})", options.bots[slot], slot + 1),
+      standing.wins,
+      standing.points,
+      standing.ticks_alive / options.rounds.max(1) as u64
+    );
+  }
+}
@@ -0,0 +1,169 @@
+//! The interface for computer players. A bot looks at a read-only view of the arena
+//! every tick and answers with a turn, just like a player pressing a key. Bots are
+//! registered by name so the lobby and the tournament runner can pick them.
+
+use rand::{rngs::StdRng, SeedableRng};
+use serde::Serialize;
+
+use crate::{ai, sim};
+
+/// Decides the moves of one snake
+pub trait Bot: Send + Sync {
+  /// The turn to take this tick, `None` to keep going straight. Only called while
+  /// the bot's snake is alive. An error takes the snake out of the round. The game
+  /// calls it on every tick, so it must not hold the game up; a bot that thinks
+  /// longer does so in the background and answers on a later tick.
+  fn decide(&mut self, view: &ArenaView) -> Result<Option<sim::Turn>, Disqualified>;
+
+  /// Blocks until a decision still being thought about in the background is ready, or
+  /// its time is up. Runners without a clock of their own, like the tournament, call
+  /// it before every `decide`, so how fast they step doesn't decide the result.
+  fn wait(&mut self) {}
+
+  /// Called before every round, the first one included. A bot disqualified in the
+  /// last round may take part again.
+  fn new_round(&mut self) {}
+}
+
+/// Why a bot was taken out of the game
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Disqualified(pub String);
+
+/// Where a snake's head is and which way it is going
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Head {
+  pub slot: usize,
+  pub position: sim::Position,
+  pub direction: sim::Direction,
+}
+
+/// What a bot gets to see of the arena on its turn
+pub struct ArenaView<'a> {
+  simulation: &'a sim::Simulation,
+  slot: usize,
+}
+
+impl<'a> ArenaView<'a> {
+  pub fn new(simulation: &'a sim::Simulation, slot: usize) -> Self {
+    ArenaView { simulation, slot }
+  }
+
+  /// The slot the bot is steering
+  pub fn slot(&self) -> usize {
+    self.slot
+  }
+
+  /// Ticks stepped since the simulation was created
+  pub fn tick(&self) -> u64 {
+    self.simulation.tick()
+  }
+
+  /// Which trail or obstacle covers each cell
+  pub fn occupancy(&self) -> &sim::Occupancy {
+    self.simulation.occupancy()
+  }
+
+  /// Heads leaving the arena come back in on the opposite edge
+  pub fn wrap_around(&self) -> bool {
+    self.simulation.wrap_around()
+  }
+
+  /// Heads of every living snake, the bot's own included, in slot order
+  pub fn heads(&self) -> impl Iterator<Item = Head> + '_ {
+    self.simulation.alive().filter_map(|slot| {
+      self.simulation.snake(slot).map(|snake| Head {
+        slot,
+        position: snake.head,
+        direction: snake.direction,
+      })
+    })
+  }
+
+  /// The bot's own head
+  pub fn me(&self) -> Option<Head> {
+    self.heads().find(|head| head.slot == self.slot)
+  }
+
+  /// Power-ups working on the bot's own snake
+  pub fn effects(&self) -> &[sim::Effect] {
+    self
+      .simulation
+      .snake(self.slot)
+      .map_or(&[], |snake| &snake.effects)
+  }
+}
+
+/// Makes a fresh bot from a seed for its random decisions
+type Factory = Box<dyn Fn(u64) -> Box<dyn Bot> + Send + Sync>;
+
+/// Every bot that can be picked, by name, in the order they were registered
+pub struct BotRegistry {
+  factories: Vec<(String, Factory)>,
+}
+
+impl BotRegistry {
+  pub fn empty() -> Self {
+    BotRegistry {
+      factories: Vec::new(),
+    }
+  }
+
+  /// The built-in AI difficulties
+  pub fn builtin() -> Self {
+    let mut registry = Self::empty();
+    for difficulty in [
+      ai::Difficulty::Easy,
+      ai::Difficulty::Medium,
+      ai::Difficulty::Hard,
+    ] {
+      registry.register(difficulty.label(), move |seed| AiBot {
+        difficulty,
+        scratch: ai::Scratch::default(),
+        rng: StdRng::seed_from_u64(seed),
+      });
+    }
+    registry
+  }
+
+  /// Adds a bot under `name`, replacing one registered under the same name before
+  pub fn register<B: Bot + 'static>(
+    &mut self,
+    name: &str,
+    factory: impl Fn(u64) -> B + Send + Sync + 'static,
+  ) {
+    let factory: Factory = Box::new(move |seed| Box::new(factory(seed)));
+    match self.factories.iter_mut().find(|(known, _)| known == name) {
+      Some((_, known)) => *known = factory,
+      None => self.factories.push((name.to_string(), factory)),
+    }
+  }
+
+  pub fn names(&self) -> impl Iterator<Item = &str> {
+    self.factories.iter().map(|(name, _)| name.as_str())
+  }
+
+  pub fn create(&self, name: &str, seed: u64) -> Option<Box<dyn Bot>> {
+    self
+      .factories
+      .iter()
+      .find(|(known, _)| known == name)
+      .map(|(_, factory)| factory(seed))
+  }
+}
+
+/// One of the built-in AI difficulties as a bot
+struct AiBot {
+  difficulty: ai::Difficulty,
+  scratch: ai::Scratch,
+  rng: StdRng,
+}
+
+impl Bot for AiBot {
+  fn decide(&mut self, view: &ArenaView) -> Result<Option<sim::Turn>, Disqualified> {
+    Ok(
+      self
+        .difficulty
+        .turn(view.simulation, view.slot, &mut self.scratch, &mut self.rng),
+    )
+  }
+}
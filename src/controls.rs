@@ -0,0 +1,369 @@
+use bevy::prelude::*;
+
+use super::despawn_screen;
+use crate::{game, init, mainmenu, roster, state};
+
+pub struct ControlsPlugin;
+
+/// Where rebound keys are kept between sessions, relative to the working directory
+const CONTROLS_FILE: &str = "controls.ron";
+
+const NAME_WIDTH: f32 = 150.0;
+const KEY_BUTTON: Vec2 = Vec2::new(80.0, 22.0);
+
+/// Keys that already do something else and can't be used for steering
+const RESERVED_KEYS: [KeyCode; 2] = [KeyCode::Escape, KeyCode::V];
+
+/// This plugin shows the controls screen where every slot's turn keys can be
+/// rebound. Bindings are saved after every change and loaded with the roster.
+impl Plugin for ControlsPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .add_state::<state::AppState>()
+      .init_resource::<Rebinding>()
+      .add_systems(
+        OnEnter(state::AppState::Controls),
+        (controls_setup, reset_rebinding),
+      )
+      .add_systems(
+        Update,
+        (
+          mainmenu::button_colors,
+          controls_buttons,
+          capture_key,
+          binding_labels,
+          message_text,
+        )
+          .chain()
+          .run_if(in_state(state::AppState::Controls)),
+      )
+      .add_systems(
+        OnExit(state::AppState::Controls),
+        despawn_screen::<OnControls>,
+      );
+  }
+}
+
+// Tag component used to tag entities added on the controls screen
+#[derive(Component)]
+struct OnControls;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Binding {
+  Left,
+  Right,
+  Up,
+  Down,
+}
+
+impl Binding {
+  const ALL: [Binding; 4] = [Binding::Left, Binding::Right, Binding::Up, Binding::Down];
+
+  fn label(self) -> &'static str {
+    match self {
+      Binding::Left => "Left",
+      Binding::Right => "Right",
+      Binding::Up => "Up",
+      Binding::Down => "Down",
+    }
+  }
+
+  fn key(self, keys: &mut roster::TurnKeys) -> &mut KeyCode {
+    match self {
+      Binding::Left => &mut keys.left,
+      Binding::Right => &mut keys.right,
+      Binding::Up => &mut keys.up,
+      Binding::Down => &mut keys.down,
+    }
+  }
+}
+
+#[derive(Component, Clone, Copy)]
+enum ControlsButton {
+  Bind(game::PlayerTag, Binding),
+  Back,
+}
+
+#[derive(Component)]
+struct ControlsMessage;
+
+/// The binding waiting for a key press, and feedback about the last attempt
+#[derive(Resource, Default)]
+struct Rebinding {
+  target: Option<(game::PlayerTag, Binding)>,
+  message: String,
+}
+
+/// Short label for a key as shown in menus
+pub fn key_name(key: KeyCode) -> String {
+  match key {
+    KeyCode::Left => "←".to_string(),
+    KeyCode::Right => "→".to_string(),
+    KeyCode::Up => "↑".to_string(),
+    KeyCode::Down => "↓".to_string(),
+    // Number row keys are called Key0 to Key9
+    _ => {
+      let name = format!("{:?}", key);
+      name.strip_prefix("Key").unwrap_or(&name).to_string()
+    }
+  }
+}
+
+/// Replaces the default bindings in `roster` with the ones saved in the controls file,
+/// if there is one
+pub fn load_bindings(roster: &mut roster::Roster) {
+  let Ok(saved) = std::fs::read_to_string(CONTROLS_FILE) else {
+    return;
+  };
+  match ron::from_str::<Vec<roster::TurnKeys>>(&saved) {
+    Ok(bindings) => {
+      for (slot, keys) in roster.slots.iter_mut().zip(bindings) {
+        slot.keys = keys;
+      }
+    }
+    Err(error) => warn!("Ignoring {}: {}", CONTROLS_FILE, error),
+  }
+}
+
+fn save_bindings(roster: &roster::Roster) {
+  let bindings: Vec<roster::TurnKeys> = roster.slots.iter().map(|slot| slot.keys).collect();
+  let saved = ron::ser::to_string_pretty(&bindings, ron::ser::PrettyConfig::default())
+    .map_err(|error| error.to_string())
+    .and_then(|saved| std::fs::write(CONTROLS_FILE, saved).map_err(|error| error.to_string()));
+  if let Err(error) = saved {
+    warn!("Could not save {}: {}", CONTROLS_FILE, error);
+  }
+}
+
+/// The slot and binding that already use `key`, other than the binding being changed.
+/// Up and down keys count even for slots using relative steering.
+fn conflict(
+  roster: &roster::Roster,
+  key: KeyCode,
+  target: (game::PlayerTag, Binding),
+) -> Option<(game::PlayerTag, Binding)> {
+  roster
+    .tags()
+    .flat_map(|tag| Binding::ALL.map(|binding| (tag, binding)))
+    .filter(|binding| *binding != target)
+    .find(|(tag, binding)| bound_key(roster, *tag, *binding) == key)
+}
+
+fn bound_key(roster: &roster::Roster, tag: game::PlayerTag, binding: Binding) -> KeyCode {
+  let mut keys = roster.get(tag).keys;
+  *binding.key(&mut keys)
+}
+
+fn reset_rebinding(mut rebinding: ResMut<Rebinding>) {
+  *rebinding = Rebinding::default();
+}
+
+fn controls_buttons(
+  interaction_query: Query<(&Interaction, &ControlsButton), (Changed<Interaction>, With<Button>)>,
+  roster: Res<roster::Roster>,
+  mut rebinding: ResMut<Rebinding>,
+  mut next_state: ResMut<NextState<state::AppState>>,
+) {
+  for (interaction, button) in &interaction_query {
+    if *interaction != Interaction::Pressed {
+      continue;
+    }
+    match button {
+      ControlsButton::Bind(tag, binding) => {
+        rebinding.target = Some((*tag, *binding));
+        rebinding.message = format!(
+          "Press a key for {} ({}), ESC to cancel",
+          roster.get(*tag).name,
+          binding.label().to_lowercase()
+        );
+      }
+      ControlsButton::Back => next_state.set(state::AppState::MainMenu),
+    }
+  }
+}
+
+/// Assigns the next key pressed to the binding being changed, unless it is reserved
+/// or already taken. Escape cancels, or leaves the screen when nothing is pending.
+fn capture_key(
+  keyboard_input: Res<Input<KeyCode>>,
+  mut rebinding: ResMut<Rebinding>,
+  mut roster: ResMut<roster::Roster>,
+  mut next_state: ResMut<NextState<state::AppState>>,
+) {
+  let Some(key) = keyboard_input.get_just_pressed().next().copied() else {
+    return;
+  };
+  let Some((tag, binding)) = rebinding.target else {
+    if key == KeyCode::Escape {
+      next_state.set(state::AppState::MainMenu);
+    }
+    return;
+  };
+  if key == KeyCode::Escape {
+    *rebinding = Rebinding::default();
+    return;
+  }
+  if RESERVED_KEYS.contains(&key) {
+    rebinding.message = format!("{} is reserved, pick another key", key_name(key));
+    return;
+  }
+  if let Some((owner, _)) = conflict(&roster, key, (tag, binding)) {
+    rebinding.message = format!(
+      "{} is already used by {}",
+      key_name(key),
+      roster.get(owner).name
+    );
+    return;
+  }
+  *binding.key(&mut roster.get_mut(tag).keys) = key;
+  save_bindings(&roster);
+  *rebinding = Rebinding::default();
+}
+
+fn binding_labels(
+  roster: Res<roster::Roster>,
+  rebinding: Res<Rebinding>,
+  buttons: Query<(&ControlsButton, &Children)>,
+  mut text_query: Query<&mut Text>,
+) {
+  for (button, children) in &buttons {
+    let ControlsButton::Bind(tag, binding) = *button else {
+      continue;
+    };
+    let value = if rebinding.target == Some((tag, binding)) {
+      "...".to_string()
+    } else {
+      key_name(bound_key(&roster, tag, binding))
+    };
+    let mut text = text_query.get_mut(children[0]).unwrap();
+    if text.sections[0].value != value {
+      text.sections[0].value = value;
+    }
+  }
+}
+
+fn message_text(rebinding: Res<Rebinding>, mut messages: Query<&mut Text, With<ControlsMessage>>) {
+  for mut text in messages.iter_mut() {
+    if text.sections[0].value != rebinding.message {
+      text.sections[0].value = rebinding.message.clone();
+    }
+  }
+}
+
+fn controls_setup(
+  mut commands: Commands,
+  fonts: Res<init::FontAssets>,
+  roster: Res<roster::Roster>,
+) {
+  commands
+    .spawn((
+      NodeBundle {
+        style: Style {
+          width: Val::Percent(100.0),
+          align_items: AlignItems::Center,
+          justify_content: JustifyContent::Center,
+          flex_direction: FlexDirection::Column,
+          ..default()
+        },
+        ..default()
+      },
+      OnControls,
+    ))
+    .with_children(|parent| {
+      parent.spawn(
+        TextBundle::from_section(
+          "Controls",
+          TextStyle {
+            font: fonts.overpass.clone(),
+            font_size: 20.0,
+            color: Color::WHITE,
+          },
+        )
+        .with_style(Style {
+          margin: UiRect::bottom(Val::Px(5.0)),
+          ..default()
+        }),
+      );
+      parent.spawn(NodeBundle::default()).with_children(|parent| {
+        parent.spawn(NodeBundle {
+          style: Style {
+            width: Val::Px(NAME_WIDTH),
+            ..default()
+          },
+          ..default()
+        });
+        for binding in Binding::ALL {
+          parent.spawn(
+            TextBundle::from_section(
+              binding.label(),
+              TextStyle {
+                font: fonts.overpass.clone(),
+                font_size: 12.0,
+                color: Color::GRAY,
+              },
+            )
+            .with_text_alignment(TextAlignment::Center)
+            .with_style(Style {
+              width: Val::Px(KEY_BUTTON.x + KEY_BUTTON.y / 4.0),
+              justify_content: JustifyContent::Center,
+              ..default()
+            }),
+          );
+        }
+      });
+      for tag in roster.tags() {
+        let slot = roster.get(tag);
+        parent
+          .spawn(NodeBundle {
+            style: Style {
+              align_items: AlignItems::Center,
+              ..default()
+            },
+            ..default()
+          })
+          .with_children(|parent| {
+            parent.spawn(
+              TextBundle::from_section(
+                slot.name.clone(),
+                TextStyle {
+                  font: fonts.overpass.clone(),
+                  font_size: 14.0,
+                  color: slot.color,
+                },
+              )
+              .with_style(Style {
+                width: Val::Px(NAME_WIDTH),
+                ..default()
+              }),
+            );
+            for binding in Binding::ALL {
+              mainmenu::spawn_sized_button(
+                parent,
+                &fonts,
+                "",
+                KEY_BUTTON,
+                14.0,
+                ControlsButton::Bind(tag, binding),
+              );
+            }
+          });
+      }
+      parent.spawn((
+        TextBundle::from_section(
+          "",
+          TextStyle {
+            font: fonts.overpass.clone(),
+            font_size: 12.0,
+            color: Color::GRAY,
+          },
+        )
+        .with_style(Style {
+          height: Val::Px(16.0),
+          margin: UiRect::vertical(Val::Px(4.0)),
+          ..default()
+        }),
+        ControlsMessage,
+      ));
+      mainmenu::spawn_button(parent, &fonts, "Back", ControlsButton::Back);
+    });
+}
@@ -0,0 +1,462 @@
+use bevy::{prelude::*, sprite::Anchor};
+use std::collections::{BTreeSet, HashMap};
+
+use super::despawn_screen;
+use crate::{game, init, levels, roster, sim, state};
+
+pub struct EditorPlugin;
+
+/// Walls are painted in square blocks of this many cells
+const BLOCK: i32 = 4;
+/// Where the edited level is saved
+const LEVEL_FILE: &str = "assets/levels/custom.ron";
+const LEVEL_NAME: &str = "Custom";
+const WALL_COLOR: Color = Color::rgb(0.35, 0.35, 0.4);
+
+/// This plugin is the level editor: walls are painted on the arena with the mouse,
+/// spawn points placed with the keyboard, and the result can be played right away
+/// or saved as a level file. The level survives leaving the editor.
+impl Plugin for EditorPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .add_state::<state::AppState>()
+      .init_resource::<EditorLevel>()
+      .init_resource::<BlockSprites>()
+      .add_systems(OnEnter(state::AppState::Editor), editor_setup)
+      .add_systems(
+        Update,
+        (
+          paint_walls,
+          place_spawns,
+          editor_commands,
+          spawn_sprites,
+          status_text,
+          game::position_translation,
+        )
+          .chain()
+          .run_if(in_state(state::AppState::Editor)),
+      )
+      .add_systems(
+        OnExit(state::AppState::Editor),
+        (despawn_screen::<OnEditor>, clear_block_sprites),
+      );
+  }
+}
+
+// Tag component used to tag entities added on the editor screen
+#[derive(Component)]
+struct OnEditor;
+
+#[derive(Component)]
+struct SpawnSprite;
+
+#[derive(Component)]
+struct EditorStatus;
+
+/// The level being edited
+#[derive(Resource)]
+struct EditorLevel {
+  /// Painted wall blocks, in block coordinates
+  blocks: BTreeSet<(i32, i32)>,
+  spawns: Vec<sim::SpawnPoint>,
+  /// Direction of the next spawn point placed
+  direction: sim::Direction,
+  /// Feedback about the last command
+  message: String,
+  /// `LEVEL_FILE` has been read in, which happens the first time the editor opens
+  loaded: bool,
+}
+
+impl Default for EditorLevel {
+  fn default() -> Self {
+    EditorLevel {
+      blocks: BTreeSet::new(),
+      spawns: Vec::new(),
+      direction: sim::Direction::Right,
+      message: String::new(),
+      loaded: false,
+    }
+  }
+}
+
+impl EditorLevel {
+  /// Picks up `level` for editing. Obstacles are cut into blocks; cells off the block
+  /// grid fill their whole block.
+  fn from_level(level: &sim::Level) -> Self {
+    EditorLevel {
+      blocks: level
+        .obstacles
+        .iter()
+        .flat_map(|obstacle| obstacle.cells())
+        .map(block_of)
+        .collect(),
+      spawns: level.spawns.clone(),
+      ..default()
+    }
+  }
+
+  /// The level in file form. Neighbouring blocks in a row become one obstacle.
+  fn to_level(&self) -> sim::Level {
+    // Row by row, so the blocks of a row come one after the other
+    let rows: BTreeSet<(i32, i32)> = self.blocks.iter().map(|&(x, y)| (y, x)).collect();
+    let mut obstacles: Vec<sim::Obstacle> = Vec::new();
+    for (y, x) in rows {
+      match obstacles.last_mut() {
+        Some(last) if last.y == y * BLOCK && last.x + last.width == x * BLOCK => {
+          last.width += BLOCK;
+        }
+        _ => obstacles.push(sim::Obstacle {
+          x: x * BLOCK,
+          y: y * BLOCK,
+          width: BLOCK,
+          height: BLOCK,
+        }),
+      }
+    }
+    sim::Level {
+      name: LEVEL_NAME.to_string(),
+      obstacles,
+      spawns: self.spawns.clone(),
+    }
+  }
+}
+
+/// Sprite of every painted block, so painting only touches the blocks that changed
+#[derive(Resource, Default)]
+struct BlockSprites(HashMap<(i32, i32), Entity>);
+
+fn block_of(position: sim::Position) -> (i32, i32) {
+  (position.x / BLOCK, position.y / BLOCK)
+}
+
+fn spawn_block(commands: &mut Commands, (x, y): (i32, i32)) -> Entity {
+  commands
+    .spawn((
+      SpriteBundle {
+        sprite: Sprite {
+          color: WALL_COLOR,
+          custom_size: Some(Vec2::splat(BLOCK as f32)),
+          anchor: Anchor::BottomLeft,
+          ..default()
+        },
+        ..default()
+      },
+      game::Position(sim::Position {
+        x: x * BLOCK,
+        y: y * BLOCK,
+      }),
+      OnEditor,
+    ))
+    .id()
+}
+
+fn editor_setup(
+  mut commands: Commands,
+  fonts: Res<init::FontAssets>,
+  mut editor: ResMut<EditorLevel>,
+  mut block_sprites: ResMut<BlockSprites>,
+) {
+  // Carry on with the saved level rather than starting empty and overwriting it
+  if !editor.loaded {
+    *editor = match load_level() {
+      Ok(Some(level)) => EditorLevel {
+        message: format!("Loaded {}", LEVEL_FILE),
+        ..EditorLevel::from_level(&level)
+      },
+      Ok(None) => EditorLevel::default(),
+      Err(error) => EditorLevel {
+        message: format!("Could not load {}: {}", LEVEL_FILE, error),
+        ..default()
+      },
+    };
+    editor.loaded = true;
+  }
+  for block in &editor.blocks {
+    let entity = spawn_block(&mut commands, *block);
+    block_sprites.0.insert(*block, entity);
+  }
+  commands
+    .spawn((
+      NodeBundle {
+        style: Style {
+          width: Val::Percent(100.0),
+          height: Val::Percent(100.0),
+          flex_direction: FlexDirection::Column,
+          justify_content: JustifyContent::SpaceBetween,
+          padding: UiRect::all(Val::Px(5.0)),
+          ..default()
+        },
+        ..default()
+      },
+      OnEditor,
+    ))
+    .with_children(|parent| {
+      parent.spawn(TextBundle::from_section(
+        "Left mouse: wall  Right mouse: erase  Arrows: direction  S: spawn point\n\
+         Backspace: remove spawn  C: clear  Enter: test  F2: save  ESC: menu",
+        TextStyle {
+          font: fonts.overpass.clone(),
+          font_size: 10.0,
+          color: Color::GRAY,
+        },
+      ));
+      parent.spawn((
+        TextBundle::from_section(
+          "",
+          TextStyle {
+            font: fonts.overpass.clone(),
+            font_size: 12.0,
+            color: Color::WHITE,
+          },
+        ),
+        EditorStatus,
+      ));
+    });
+}
+
+fn clear_block_sprites(mut block_sprites: ResMut<BlockSprites>) {
+  block_sprites.0.clear();
+}
+
+/// Left mouse paints walls under the cursor, right mouse erases them. Spawn points
+/// keep their cell free.
+fn paint_walls(
+  mut commands: Commands,
+  windows: Query<&Window>,
+  mouse_input: Res<Input<MouseButton>>,
+  mut editor: ResMut<EditorLevel>,
+  mut block_sprites: ResMut<BlockSprites>,
+) {
+  let Some(position) = game::cursor_position(windows.single()) else {
+    return;
+  };
+  let block = block_of(position);
+  if mouse_input.pressed(MouseButton::Left) {
+    let on_spawn = editor
+      .spawns
+      .iter()
+      .any(|spawn| block_of(spawn.position) == block);
+    if !on_spawn && editor.blocks.insert(block) {
+      let entity = spawn_block(&mut commands, block);
+      block_sprites.0.insert(block, entity);
+    }
+  } else if mouse_input.pressed(MouseButton::Right) && editor.blocks.remove(&block) {
+    if let Some(entity) = block_sprites.0.remove(&block) {
+      commands.entity(entity).despawn();
+    }
+  }
+}
+
+/// Arrow keys pick the direction, S places a spawn point for the next slot under the
+/// cursor and Backspace takes the last one away
+fn place_spawns(
+  windows: Query<&Window>,
+  keyboard_input: Res<Input<KeyCode>>,
+  mut editor: ResMut<EditorLevel>,
+) {
+  for (key, direction) in [
+    (KeyCode::Left, sim::Direction::Left),
+    (KeyCode::Right, sim::Direction::Right),
+    (KeyCode::Up, sim::Direction::Up),
+    (KeyCode::Down, sim::Direction::Down),
+  ] {
+    if keyboard_input.just_pressed(key) {
+      editor.direction = direction;
+    }
+  }
+  if keyboard_input.just_pressed(KeyCode::Back) {
+    editor.spawns.pop();
+  }
+  if !keyboard_input.just_pressed(KeyCode::S) {
+    return;
+  }
+  let Some(position) = game::cursor_position(windows.single()) else {
+    return;
+  };
+  if editor.spawns.len() >= roster::MAX_PLAYERS {
+    editor.message = format!("All {} spawn points are placed", roster::MAX_PLAYERS);
+  } else if editor.blocks.contains(&block_of(position)) {
+    editor.message = "Spawn points can't be inside a wall".to_string();
+  } else {
+    let direction = editor.direction;
+    editor.spawns.push(sim::SpawnPoint {
+      position,
+      direction,
+    });
+  }
+}
+
+/// Puts the edited level into the lobby's level list, replacing an earlier version,
+/// and returns its index there
+fn publish(levels: &mut levels::Levels, level: sim::Level) -> usize {
+  match levels.0.iter().position(|known| known.name == level.name) {
+    Some(index) => {
+      levels.0[index] = level;
+      index
+    }
+    None => {
+      levels.0.push(level);
+      levels.0.len() - 1
+    }
+  }
+}
+
+/// The level saved before, `None` if nothing has been saved yet
+fn load_level() -> Result<Option<sim::Level>, String> {
+  match std::fs::read_to_string(LEVEL_FILE) {
+    Ok(level) => ron::from_str(&level)
+      .map(Some)
+      .map_err(|error| error.to_string()),
+    Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+    Err(error) => Err(error.to_string()),
+  }
+}
+
+fn save_level(level: &sim::Level) -> Result<(), String> {
+  let saved = ron::ser::to_string_pretty(level, ron::ser::PrettyConfig::default())
+    .map_err(|error| error.to_string())?;
+  std::fs::create_dir_all("assets/levels").map_err(|error| error.to_string())?;
+  std::fs::write(LEVEL_FILE, saved).map_err(|error| error.to_string())
+}
+
+fn editor_commands(
+  mut commands: Commands,
+  keyboard_input: Res<Input<KeyCode>>,
+  mut editor: ResMut<EditorLevel>,
+  mut block_sprites: ResMut<BlockSprites>,
+  mut levels: ResMut<levels::Levels>,
+  mut settings: ResMut<state::MatchSettings>,
+  mut next_state: ResMut<NextState<state::AppState>>,
+) {
+  if keyboard_input.just_pressed(KeyCode::Escape) {
+    next_state.set(state::AppState::MainMenu);
+  } else if keyboard_input.just_pressed(KeyCode::C) {
+    editor.blocks.clear();
+    editor.spawns.clear();
+    for (_, entity) in block_sprites.0.drain() {
+      commands.entity(entity).despawn();
+    }
+  } else if keyboard_input.just_pressed(KeyCode::Return) {
+    settings.level = publish(&mut levels, editor.to_level());
+    next_state.set(state::AppState::Game);
+  } else if keyboard_input.just_pressed(KeyCode::F2) {
+    let level = editor.to_level();
+    editor.message = match save_level(&level) {
+      Ok(()) => format!("Saved to {}", LEVEL_FILE),
+      Err(error) => format!("Could not save: {}", error),
+    };
+    publish(&mut levels, level);
+  }
+}
+
+/// Shows every spawn point as the head of the slot that starts there, with a line
+/// pointing the way it will move
+fn spawn_sprites(
+  mut commands: Commands,
+  editor: Res<EditorLevel>,
+  roster: Res<roster::Roster>,
+  sprites: Query<Entity, With<SpawnSprite>>,
+) {
+  // Also redraw when coming back to the editor, which starts without sprites
+  let missing = sprites.is_empty() && !editor.spawns.is_empty();
+  if !editor.is_changed() && !missing {
+    return;
+  }
+  for entity in sprites.iter() {
+    commands.entity(entity).despawn_recursive();
+  }
+  for (slot, spawn) in roster.slots.iter().zip(editor.spawns.iter()) {
+    let (dx, dy) = match spawn.direction {
+      sim::Direction::Left => (-1.0, 0.0),
+      sim::Direction::Right => (1.0, 0.0),
+      sim::Direction::Up => (0.0, 1.0),
+      sim::Direction::Down => (0.0, -1.0),
+    };
+    commands
+      .spawn((
+        SpriteBundle {
+          sprite: Sprite {
+            color: slot.tint,
+            ..default()
+          },
+          texture: slot.sprite.clone(),
+          transform: Transform::from_translation(Vec3::new(0., 0., 1.)),
+          ..default()
+        },
+        game::Position(spawn.position),
+        SpawnSprite,
+        OnEditor,
+      ))
+      .with_children(|parent| {
+        parent.spawn(SpriteBundle {
+          sprite: Sprite {
+            color: slot.color,
+            custom_size: Some(Vec2::new(12.0 * dx, 12.0 * dy) + Vec2::splat(2.0)),
+            anchor: Anchor::Custom(Vec2::new(-dx, -dy) / 2.0),
+            ..default()
+          },
+          transform: Transform::from_translation(Vec3::new(0., 0., -0.5)),
+          ..default()
+        });
+      });
+  }
+}
+
+fn status_text(editor: Res<EditorLevel>, mut texts: Query<&mut Text, With<EditorStatus>>) {
+  let status = format!(
+    "Next spawn faces {:?}, {} of {} placed. {}",
+    editor.direction,
+    editor.spawns.len(),
+    roster::MAX_PLAYERS,
+    editor.message
+  );
+  for mut text in texts.iter_mut() {
+    if text.sections[0].value != status {
+      text.sections[0].value = status.clone();
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn blocks_in_a_row_become_one_obstacle() {
+    let editor = EditorLevel {
+      blocks: [(2, 1), (3, 1), (4, 1), (2, 2), (6, 1)]
+        .into_iter()
+        .collect(),
+      ..default()
+    };
+    let obstacles: Vec<(i32, i32, i32)> = editor
+      .to_level()
+      .obstacles
+      .iter()
+      .map(|obstacle| (obstacle.x, obstacle.y, obstacle.width))
+      .collect();
+    assert_eq!(
+      obstacles,
+      [
+        (2 * BLOCK, BLOCK, 3 * BLOCK),
+        (6 * BLOCK, BLOCK, BLOCK),
+        (2 * BLOCK, 2 * BLOCK, BLOCK)
+      ]
+    );
+  }
+
+  #[test]
+  fn a_saved_level_comes_back_as_blocks() {
+    let editor = EditorLevel {
+      blocks: [(2, 1), (3, 1), (3, 2)].into_iter().collect(),
+      spawns: vec![sim::SpawnPoint {
+        position: sim::Position { x: 100, y: 50 },
+        direction: sim::Direction::Up,
+      }],
+      ..default()
+    };
+    let loaded = EditorLevel::from_level(&editor.to_level());
+    assert_eq!(loaded.blocks, editor.blocks);
+    assert_eq!(loaded.spawns.len(), 1);
+  }
+}
@@ -0,0 +1,101 @@
+use bevy::prelude::*;
+
+use crate::{game, sim};
+
+pub struct EventsPlugin;
+
+/// This plugin registers the gameplay events. The player and round systems send them
+/// as things happen; HUD, audio, effects, statistics or replays can each read them
+/// without the senders knowing.
+impl Plugin for EventsPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .add_event::<PlayerSpawned>()
+      .add_event::<PlayerTurned>()
+      .add_event::<PlayerEliminated>()
+      .add_event::<RoundStarted>()
+      .add_event::<RoundEnded>()
+      .add_systems(Update, log_events);
+  }
+}
+
+/// A player's snake entered the arena
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PlayerSpawned {
+  pub tag: game::PlayerTag,
+  pub position: sim::Position,
+  pub direction: sim::Direction,
+}
+
+/// A player's snake moved off in a new direction during a tick
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PlayerTurned {
+  pub tag: game::PlayerTag,
+  pub direction: sim::Direction,
+}
+
+/// A player's snake went out. Sent from the fixed-rate tick it happened in, so the
+/// simulation still shows the arena right after that tick while it is read there.
+#[derive(Event, Debug, Clone)]
+pub struct PlayerEliminated {
+  pub tag: game::PlayerTag,
+  pub cause: sim::Cause,
+  /// Owner of the trail they ran into, if it wasn't their own
+  pub killer: Option<game::PlayerTag>,
+  /// Slots still in the round right after the elimination, the ones who outlived it
+  pub survivors: Vec<usize>,
+}
+
+/// The countdown finished and everyone who joined is in the arena
+#[derive(Event, Debug, Clone, Copy)]
+pub struct RoundStarted {
+  /// Counting from 1
+  pub round: usize,
+}
+
+/// One or no snakes are left
+#[derive(Event, Debug, Clone, Copy)]
+pub struct RoundEnded {
+  pub round: usize,
+  pub winner: Option<game::PlayerTag>,
+}
+
+/// Writes every gameplay event to the log; turns only show up at debug level
+fn log_events(
+  mut spawned: EventReader<PlayerSpawned>,
+  mut turned: EventReader<PlayerTurned>,
+  mut eliminated: EventReader<PlayerEliminated>,
+  mut started: EventReader<RoundStarted>,
+  mut ended: EventReader<RoundEnded>,
+) {
+  for event in started.iter() {
+    info!("Round {} started", event.round);
+  }
+  for event in spawned.iter() {
+    info!(
+      "Player {} spawned at {:?} facing {:?}",
+      event.tag.index(),
+      event.position,
+      event.direction
+    );
+  }
+  for event in turned.iter() {
+    debug!("Player {} turned {:?}", event.tag.index(), event.direction);
+  }
+  for event in eliminated.iter() {
+    match event.killer {
+      Some(killer) => info!(
+        "Player {} ran into player {}'s trail",
+        event.tag.index(),
+        killer.index()
+      ),
+      None => info!("Player {} went out: {:?}", event.tag.index(), event.cause),
+    }
+  }
+  for event in ended.iter() {
+    match event.winner {
+      Some(winner) => info!("Round {} won by player {}", event.round, winner.index()),
+      None => info!("Round {} ended in a draw", event.round),
+    }
+  }
+}
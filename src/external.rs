@@ -0,0 +1,475 @@
+//! Bots running as separate programs, so they can be written in any language.
+//!
+//! The game starts the program and talks to it in lines of JSON. Every tick its snake
+//! is alive, the program gets one line on its standard input:
+//!
+//! ```json
+//! {"tick":7,"you":1,"width":640,"height":360,"wrap_around":false,"reset":false,
+//!  "heads":[{"slot":0,"position":{"x":50,"y":180},"direction":"Right"}, ...],
+//!  "effects":[{"power_up":"Wrap","ticks_left":120}],
+//!  "filled":[[50,180,0],[300,100,-1]],"freed":[[12,40]]}
+//! ```
+//!
+//! `effects` lists the power-ups working on the program's own snake and how many ticks
+//! each has left: `Speed` moves it three cells every two ticks, `Slow` (from another
+//! snake's pickup) one cell every two ticks, `Ghost` lets it pass through trails and
+//! `Wrap` brings it back in on the opposite edge, whatever `wrap_around` says.
+//!
+//! `filled` lists the cells covered since the previous line as `[x, y, owner]`, with
+//! the owner's slot or -1 for an obstacle, and `freed` the cells uncovered since. With
+//! `reset` the program should forget every cell it knows: `filled` then lists
+//! everything covered, obstacles included. That is the case on the first line of every
+//! round and whenever the game can't tell what changed.
+//!
+//! The program answers with one line on its standard output, either
+//! `{"turn":"left"}`, `{"turn":"right"}` and `{"turn":"straight"}` or `{"face":"Up"}`
+//! with any direction. The game doesn't wait for it: the turn is taken on the first
+//! tick after the answer arrives, the snake going straight until then, and the next
+//! line is only sent along with it. So by the time an answer is taken, the snake has
+//! made one more move under the previous answer, which a relative turn also builds on.
+//! Anything else, an answer out of turn or one later than the time limit disqualifies
+//! the bot for the rest of the round. The first line of a round may take up to
+//! `STARTUP_LIMIT` to answer, since it may have to start the program.
+
+use serde::{Deserialize, Serialize};
+use std::{
+  io::{BufRead, BufReader, Write},
+  process::{Child, Command, Stdio},
+  sync::{
+    mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    Mutex,
+  },
+  time::{Duration, Instant},
+};
+
+use crate::{bot, sim};
+
+/// The file listing the external bots, relative to the working directory
+pub const CONFIG_FILE: &str = "bots/bots.ron";
+/// Time a program gets for its first answer of a round, which includes starting up
+const STARTUP_LIMIT: Duration = Duration::from_secs(2);
+
+/// One external bot in `CONFIG_FILE`
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExternalBotConfig {
+  /// Shown in the lobby and used to name the bot in tournaments
+  pub name: String,
+  /// The program and its arguments
+  pub command: Vec<String>,
+  /// Milliseconds the program gets to answer each line
+  #[serde(default = "default_time_limit")]
+  pub time_limit_ms: u64,
+}
+
+fn default_time_limit() -> u64 {
+  50
+}
+
+impl ExternalBotConfig {
+  /// Makes the bot available in `registry`. Every bot created from it starts its own
+  /// copy of the program.
+  pub fn register(self, registry: &mut bot::BotRegistry) {
+    let time_limit = Duration::from_millis(self.time_limit_ms);
+    registry.register(&self.name, move |_| {
+      ExternalBot::start(&self.command, time_limit)
+    });
+  }
+}
+
+/// Reads the external bots from `path`. A missing file means there are none.
+pub fn load_config(path: &str) -> Result<Vec<ExternalBotConfig>, String> {
+  match std::fs::read_to_string(path) {
+    Ok(config) => ron::from_str(&config).map_err(|error| format!("{}: {}", path, error)),
+    Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+    Err(error) => Err(format!("{}: {}", path, error)),
+  }
+}
+
+/// A cell as `[x, y, owner]`, the owner being a slot or -1 for an obstacle
+type FilledCell = (i32, i32, i32);
+/// A cell as `[x, y]`
+type FreedCell = (i32, i32);
+
+/// What the program gets every tick
+#[derive(Serialize)]
+struct TickMessage {
+  tick: u64,
+  you: usize,
+  width: u32,
+  height: u32,
+  wrap_around: bool,
+  reset: bool,
+  heads: Vec<bot::Head>,
+  effects: Vec<sim::Effect>,
+  filled: Vec<FilledCell>,
+  freed: Vec<FreedCell>,
+}
+
+/// What the program answers
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase", deny_unknown_fields)]
+enum Answer {
+  Turn(Relative),
+  Face(sim::Direction),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Relative {
+  Left,
+  Right,
+  Straight,
+}
+
+/// The running program
+struct Process {
+  child: Child,
+  /// Lines for the program's standard input, written on their own thread so a program
+  /// that stops reading can't hold up the game
+  messages: Sender<String>,
+  /// Lines from the program's standard output, read on their own thread so waiting
+  /// for them can time out. Behind a mutex only so bots can be shared between threads.
+  answers: Mutex<Receiver<String>>,
+}
+
+pub struct ExternalBot {
+  command: Vec<String>,
+  /// `None` once the bot has been disqualified
+  process: Option<Process>,
+  time_limit: Duration,
+  /// The next message is the first of a round
+  round_start: bool,
+  /// `sim::Occupancy::mark` of the arena as last sent, `None` to send all of it
+  sent: Option<u64>,
+  /// When the message still waiting for its answer was sent
+  waiting: Option<Instant>,
+  /// The answer to the waiting message, if `bot::Bot::wait` already received it
+  early: Option<String>,
+  /// Answers still to come to messages of an earlier round, to be ignored
+  stale: usize,
+  disqualified: Option<String>,
+}
+
+impl ExternalBot {
+  /// Starts the program. A program that can't be started is disqualified right away.
+  pub fn start(command: &[String], time_limit: Duration) -> Self {
+    let mut bot = ExternalBot {
+      command: command.to_vec(),
+      process: None,
+      time_limit,
+      round_start: true,
+      sent: None,
+      waiting: None,
+      early: None,
+      stale: 0,
+      disqualified: None,
+    };
+    bot.restart();
+    bot
+  }
+
+  /// Starts the program again unless it is still running, and lifts a disqualification
+  fn restart(&mut self) {
+    self.disqualified = None;
+    if self.process.is_some() {
+      return;
+    }
+    self.waiting = None;
+    self.early = None;
+    self.stale = 0;
+    match Self::spawn(&self.command) {
+      Ok(process) => self.process = Some(process),
+      Err(error) => {
+        self.disqualified = Some(format!("could not start {:?}: {}", self.command, error))
+      }
+    }
+  }
+
+  fn spawn(command: &[String]) -> std::io::Result<Process> {
+    let (program, args) = command
+      .split_first()
+      .ok_or_else(|| std::io::Error::other("the command is empty"))?;
+    let mut child = Command::new(program)
+      .args(args)
+      .stdin(Stdio::piped())
+      .stdout(Stdio::piped())
+      .spawn()?;
+    let mut stdin = child.stdin.take().unwrap();
+    let stdout = child.stdout.take().unwrap();
+    let (messages, outgoing) = mpsc::channel::<String>();
+    std::thread::spawn(move || {
+      for message in outgoing {
+        if writeln!(stdin, "{}", message)
+          .and_then(|_| stdin.flush())
+          .is_err()
+        {
+          break;
+        }
+      }
+    });
+    let (sender, answers) = mpsc::channel();
+    std::thread::spawn(move || {
+      for line in BufReader::new(stdout).lines() {
+        let Ok(line) = line else {
+          break;
+        };
+        if sender.send(line).is_err() {
+          break;
+        }
+      }
+    });
+    Ok(Process {
+      child,
+      messages,
+      answers: Mutex::new(answers),
+    })
+  }
+
+  /// The cells to send: the `changed` ones as they are now, or everything covered
+  /// when there is no telling what changed
+  fn cells(
+    occupancy: &sim::Occupancy,
+    changed: Option<impl Iterator<Item = sim::Position>>,
+  ) -> (Vec<FilledCell>, Vec<FreedCell>) {
+    let positions: Vec<sim::Position> = match changed {
+      Some(changed) => {
+        let mut changed: Vec<sim::Position> = changed.collect();
+        // A cell may have changed more than once; only how it is now matters
+        changed.sort_by_key(|position| (position.y, position.x));
+        changed.dedup();
+        changed
+      }
+      None => (0..sim::ARENA_HEIGHT as i32)
+        .flat_map(|y| (0..sim::ARENA_WIDTH as i32).map(move |x| sim::Position { x, y }))
+        .filter(|position| !occupancy.is_free(*position))
+        .collect(),
+    };
+    let mut filled = Vec::new();
+    let mut freed = Vec::new();
+    for position in positions {
+      let sim::Position { x, y } = position;
+      match occupancy.is_obstacle(position) {
+        true => filled.push((x, y, -1)),
+        false => match occupancy.get(position) {
+          Some(slot) => filled.push((x, y, slot as i32)),
+          None => freed.push((x, y)),
+        },
+      }
+    }
+    (filled, freed)
+  }
+
+  /// Time the waiting message has to be answered in
+  fn limit(&self) -> Duration {
+    match self.round_start {
+      true => STARTUP_LIMIT,
+      false => self.time_limit,
+    }
+  }
+
+  /// The next line from the program, waiting for it up to `timeout`
+  fn receive(&mut self, timeout: Duration) -> Result<Option<String>, String> {
+    if let Some(line) = self.early.take() {
+      return Ok(Some(line));
+    }
+    let answers = self.process.as_mut().unwrap().answers.get_mut().unwrap();
+    match answers.recv_timeout(timeout) {
+      Ok(line) => Ok(Some(line)),
+      Err(RecvTimeoutError::Timeout) => Ok(None),
+      Err(RecvTimeoutError::Disconnected) => Err("exited".to_string()),
+    }
+  }
+
+  /// Takes the answer to the last message if it has arrived, and sends the arena
+  /// along once there is no message waiting any more
+  fn exchange(&mut self, view: &bot::ArenaView) -> Result<Option<sim::Turn>, String> {
+    let mut turn = None;
+    while let Some(line) = self.receive(Duration::ZERO)? {
+      if self.stale > 0 {
+        self.stale -= 1;
+        continue;
+      }
+      if self.waiting.take().is_none() {
+        return Err(format!("answered {:?} out of turn", line));
+      }
+      self.round_start = false;
+      let answer: Answer =
+        serde_json::from_str(&line).map_err(|error| format!("answered {:?}: {}", line, error))?;
+      turn = match answer {
+        Answer::Turn(Relative::Left) => Some(sim::Turn::Left),
+        Answer::Turn(Relative::Right) => Some(sim::Turn::Right),
+        Answer::Turn(Relative::Straight) => None,
+        Answer::Face(direction) => Some(sim::Turn::Face(direction)),
+      };
+    }
+    if let Some(sent) = self.waiting {
+      let limit = self.limit();
+      if sent.elapsed() >= limit {
+        return Err(format!("took longer than {} ms", limit.as_millis()));
+      }
+      return Ok(None);
+    }
+
+    let occupancy = view.occupancy();
+    let changed = self.sent.and_then(|sent| occupancy.changed_since(sent));
+    let reset = changed.is_none();
+    let (filled, freed) = Self::cells(occupancy, changed);
+    self.sent = Some(occupancy.mark());
+    let message = TickMessage {
+      tick: view.tick(),
+      you: view.slot(),
+      width: sim::ARENA_WIDTH,
+      height: sim::ARENA_HEIGHT,
+      wrap_around: view.wrap_around(),
+      reset,
+      heads: view.heads().collect(),
+      effects: view.effects().to_vec(),
+      filled,
+      freed,
+    };
+    let message = serde_json::to_string(&message).map_err(|error| error.to_string())?;
+    self
+      .process
+      .as_mut()
+      .unwrap()
+      .messages
+      .send(message)
+      .map_err(|_| "stopped reading".to_string())?;
+    self.waiting = Some(Instant::now());
+    Ok(turn)
+  }
+
+  fn stop(&mut self) {
+    if let Some(mut process) = self.process.take() {
+      // The program may have exited already
+      let _ = process.child.kill();
+      let _ = process.child.wait();
+    }
+  }
+}
+
+impl bot::Bot for ExternalBot {
+  fn decide(&mut self, view: &bot::ArenaView) -> Result<Option<sim::Turn>, bot::Disqualified> {
+    if let Some(reason) = &self.disqualified {
+      return Err(bot::Disqualified(reason.clone()));
+    }
+    self.exchange(view).map_err(|reason| {
+      self.stop();
+      self.disqualified = Some(reason.clone());
+      bot::Disqualified(reason)
+    })
+  }
+
+  /// Waits for the answer to the last message, at most until its time is up
+  fn wait(&mut self) {
+    let Some(sent) = self.waiting.filter(|_| self.disqualified.is_none()) else {
+      return;
+    };
+    while self.early.is_none() {
+      let remaining = self.limit().saturating_sub(sent.elapsed());
+      match self.receive(remaining) {
+        Ok(Some(_)) if self.stale > 0 => self.stale -= 1,
+        Ok(Some(line)) => self.early = Some(line),
+        // `decide` finds out about these again
+        Ok(None) | Err(_) => break,
+      }
+    }
+  }
+
+  /// A bot disqualified in the last round gets a fresh copy of its program
+  fn new_round(&mut self) {
+    // An answer still to come belongs to the round that just ended
+    if self.early.take().is_none() && self.waiting.is_some() {
+      self.stale += 1;
+    }
+    self.waiting = None;
+    self.restart();
+    self.round_start = true;
+    self.sent = None;
+  }
+}
+
+impl Drop for ExternalBot {
+  fn drop(&mut self) {
+    self.stop();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::bot::Bot;
+
+  #[test]
+  fn sends_the_cells_changed_since_the_last_message() {
+    let mut simulation = sim::Simulation::new(1, 0);
+    simulation.set_level(sim::Level {
+      obstacles: vec![sim::Obstacle {
+        x: 0,
+        y: 0,
+        width: 2,
+        height: 1,
+      }],
+      ..sim::Level::default()
+    });
+    simulation.set_trail_mode(sim::TrailMode::Fixed(2));
+    simulation.spawn_at(0, sim::Position { x: 10, y: 10 }, sim::Direction::Right);
+    simulation.step(&[]);
+    let occupancy = simulation.occupancy();
+    let everything = ExternalBot::cells(occupancy, None::<std::iter::Empty<sim::Position>>);
+    assert_eq!(
+      everything,
+      (vec![(0, 0, -1), (1, 0, -1), (11, 10, 0)], vec![])
+    );
+
+    let sent = occupancy.mark();
+    simulation.step(&[]);
+    simulation.step(&[]);
+    let occupancy = simulation.occupancy();
+    let changed = ExternalBot::cells(occupancy, occupancy.changed_since(sent));
+    assert_eq!(changed, (vec![(12, 10, 0), (13, 10, 0)], vec![(11, 10)]));
+  }
+
+  fn shell_bot(script: &str, time_limit_ms: u64) -> ExternalBot {
+    let command = ["sh", "-c", script].map(str::to_string);
+    ExternalBot::start(&command, Duration::from_millis(time_limit_ms))
+  }
+
+  fn arena() -> sim::Simulation {
+    let mut simulation = sim::Simulation::new(1, 0);
+    simulation.spawn_at(0, sim::Position { x: 10, y: 10 }, sim::Direction::Right);
+    simulation
+  }
+
+  #[test]
+  fn answers_are_taken_on_a_later_tick() {
+    let mut bot = shell_bot(r#"while read line; do echo '{"turn":"left"}'; done"#, 1000);
+    let simulation = arena();
+    let view = bot::ArenaView::new(&simulation, 0);
+    assert_eq!(bot.decide(&view), Ok(None));
+    bot.wait();
+    assert_eq!(bot.decide(&view), Ok(Some(sim::Turn::Left)));
+    // A new round ignores the answer to the last message of the old one
+    bot.new_round();
+    assert_eq!(bot.decide(&view), Ok(None));
+    bot.wait();
+    assert_eq!(bot.decide(&view), Ok(Some(sim::Turn::Left)));
+  }
+
+  #[test]
+  fn a_bot_missing_its_time_limit_is_disqualified() {
+    let mut bot = shell_bot("cat > /dev/null", 20);
+    bot.round_start = false;
+    let simulation = arena();
+    let view = bot::ArenaView::new(&simulation, 0);
+    assert_eq!(bot.decide(&view), Ok(None));
+    // Still thinking, without holding up the caller
+    assert_eq!(bot.decide(&view), Ok(None));
+    bot.wait();
+    assert!(bot.decide(&view).is_err());
+    // Until the next round
+    assert!(bot.decide(&view).is_err());
+    bot.new_round();
+    assert_eq!(bot.decide(&view), Ok(None));
+  }
+}
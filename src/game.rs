@@ -1,34 +1,75 @@
 use bevy::prelude::*;
 
 use super::despawn_screen;
+use crate::init;
 use crate::player;
+use crate::powerup;
+use crate::roster;
+use crate::sim;
 use crate::state;
-use bevy::app::AppExit;
+
+pub use crate::sim::{ARENA_HEIGHT, ARENA_WIDTH};
 
 pub struct GamePlugin;
 
-#[derive(Component, Debug, Clone, Copy, PartialEq)]
-pub enum PlayerTag {
-  Player1,
-  Player2,
-  Player3,
-  Player4,
+/// How many simulation ticks run per second, independent of the render frame rate.
+/// Movement, tail growth and collisions all run in `FixedUpdate` at this rate.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct TickRate(pub f32);
+
+impl Default for TickRate {
+  fn default() -> Self {
+    TickRate(60.0)
+  }
 }
 
-pub const ARENA_WIDTH: u32 = 640;
-pub const ARENA_HEIGHT: u32 = 360;
+/// The arena cell an entity is drawn on. Wraps the simulation's cell so the headless
+/// rules don't depend on Bevy.
+#[derive(Component, Deref, DerefMut, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Position(pub sim::Position);
+
+/// Where an entity was before the latest simulation tick, so it can be drawn
+/// in between the two cells while the next tick is pending
+#[derive(Component, Copy, Clone, Debug)]
+pub struct PreviousPosition(pub sim::Position);
+
+/// Identifies a player slot; the same index is used in the roster and the simulation
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PlayerTag(pub usize);
+
+impl PlayerTag {
+  pub fn index(self) -> usize {
+    self.0
+  }
+}
 
 impl Plugin for GamePlugin {
   fn build(&self, app: &mut App) {
     app
       .add_state::<state::AppState>()
-      .add_systems(OnEnter(state::AppState::Game), (test_ui, static_ui_texts))
-      .add_systems(OnExit(state::AppState::Game), despawn_screen::<OnGame>)
+      .init_resource::<TickRate>()
+      .insert_resource(FixedTime::new_from_secs(1.0 / TickRate::default().0))
+      .add_systems(
+        Update,
+        apply_tick_rate.run_if(resource_changed::<TickRate>()),
+      )
       .add_systems(
         Update,
-        (position_translation, score_update_system, ingame_input)
+        (
+          position_translation,
+          score_update_system,
+          effects_update_system,
+          hud_visibility,
+          ingame_input,
+        )
           .run_if(in_state(state::AppState::Game)),
       );
+    for transition in state::MATCH_START {
+      app.add_systems(transition, hud_setup);
+    }
+    for match_end in state::MATCH_END {
+      app.add_systems(OnEnter(match_end), despawn_screen::<OnGame>);
+    }
   }
 }
 
@@ -36,210 +77,217 @@ impl Plugin for GamePlugin {
 #[derive(Component)]
 struct OnGame;
 
-#[derive(Component, Copy, Clone, Debug, PartialEq)]
-pub struct Position {
-  pub x: i32,
-  pub y: i32,
-}
-
+/// Escape or a gamepad's Start button pauses the match
 fn ingame_input(
   keyboard_input: Res<Input<KeyCode>>,
-  mut exit: EventWriter<AppExit>
+  gamepads: Res<Gamepads>,
+  gamepad_input: Res<Input<GamepadButton>>,
+  mut next_state: ResMut<NextState<state::AppState>>,
 ) {
-  if keyboard_input.any_just_pressed([KeyCode::Escape]) {
-    println!("Thanks for playing Aninmals Tron!");
-    exit.send(AppExit);
+  if pause_pressed(&keyboard_input, &gamepads, &gamepad_input) {
+    next_state.set(state::AppState::Paused);
   }
 }
 
-fn position_translation(
+pub fn pause_pressed(
+  keyboard_input: &Input<KeyCode>,
+  gamepads: &Gamepads,
+  gamepad_input: &Input<GamepadButton>,
+) -> bool {
+  keyboard_input.just_pressed(KeyCode::Escape)
+    || gamepads.iter().any(|gamepad| {
+      gamepad_input.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::Start))
+    })
+}
+
+fn apply_tick_rate(tick_rate: Res<TickRate>, mut fixed_time: ResMut<FixedTime>) {
+  fixed_time.period = std::time::Duration::from_secs_f32(1.0 / tick_rate.0);
+}
+
+/// The arena cell under the mouse cursor, the inverse of `position_translation`
+pub fn cursor_position(window: &Window) -> Option<sim::Position> {
+  let cursor = window.cursor_position()?;
+  // Window coordinates run from the top left, arena cells from the bottom left
+  let position = sim::Position {
+    x: (cursor.x / window.width() * ARENA_WIDTH as f32) as i32,
+    y: ((window.height() - cursor.y) / window.height() * ARENA_HEIGHT as f32) as i32,
+  };
+  position.in_arena().then_some(position)
+}
+
+/// Places everything with a grid `Position` on screen. Entities that also carry a
+/// `PreviousPosition` are interpolated by how far we are into the next tick.
+pub fn position_translation(
   mut windows: Query<&mut Window>,
-  mut q: Query<(&Position, &mut Transform)>,
+  fixed_time: Res<FixedTime>,
+  mut q: Query<(&Position, Option<&PreviousPosition>, &mut Transform)>,
 ) {
   fn convert(pos: f32, bound_window: f32, bound_game: f32) -> f32 {
     let tile_size = bound_window / bound_game;
     pos / bound_game * bound_window - (bound_window / 2.) + (tile_size / 2.)
   }
   let window = windows.single_mut();
-  for (pos, mut transform) in q.iter_mut() {
+  let alpha = (fixed_time.accumulated().as_secs_f32() / fixed_time.period.as_secs_f32()).min(1.0);
+  for (pos, previous, mut transform) in q.iter_mut() {
+    let current = Vec2::new(pos.x as f32, pos.y as f32);
+    let drawn = match previous {
+      // A head that wrapped around the arena jumps instead of sliding across it
+      Some(PreviousPosition(previous))
+        if (previous.x - pos.x).abs() <= 2 && (previous.y - pos.y).abs() <= 2 =>
+      {
+        Vec2::new(previous.x as f32, previous.y as f32).lerp(current, alpha)
+      }
+      _ => current,
+    };
     transform.translation = Vec3::new(
-      convert(pos.x as f32, window.width() as f32, ARENA_WIDTH as f32),
-      convert(pos.y as f32, window.height() as f32, ARENA_HEIGHT as f32),
-      0.0,
+      convert(drawn.x, window.width(), ARENA_WIDTH as f32),
+      convert(drawn.y, window.height(), ARENA_HEIGHT as f32),
+      // Keeps heads and pickups drawn above the trail canvas
+      transform.translation.z,
     );
   }
 }
 
-fn score_update_system(
-  scores: ResMut<state::PlayerScores>,
-  mut query: Query<(&PlayerTag, &mut Text)>,
-) {
+fn score_update_system(roster: Res<roster::Roster>, mut query: Query<(&PlayerTag, &mut Text)>) {
   for (tag, mut text) in query.iter_mut() {
-    match tag {
-      PlayerTag::Player1 => text.sections[0].value = scores.player1.to_string(),
-      PlayerTag::Player2 => text.sections[0].value = scores.player2.to_string(),
-      PlayerTag::Player3 => text.sections[0].value = scores.player3.to_string(),
-      PlayerTag::Player4 => text.sections[0].value = scores.player4.to_string(),
+    text.sections[0].value = roster.get(*tag).score.to_string();
+  }
+}
+
+/// Power-ups running on a player, with the seconds they have left
+#[derive(Component)]
+struct EffectsText(PlayerTag);
+
+fn effects_update_system(
+  simulation: Res<player::Simulation>,
+  tick_rate: Res<TickRate>,
+  fonts: Res<init::FontAssets>,
+  mut query: Query<(&EffectsText, &mut Text)>,
+) {
+  for (effects_text, mut text) in query.iter_mut() {
+    let effects = simulation
+      .snake(effects_text.0.index())
+      .map_or(&[][..], |snake| &snake.effects);
+    let sections: Vec<TextSection> = effects
+      .iter()
+      .map(|effect| {
+        let seconds = (effect.ticks_left as f32 / tick_rate.0).ceil();
+        TextSection::new(
+          format!("{} {}s ", effect.power_up.label(), seconds),
+          TextStyle {
+            font: fonts.overpass.clone(),
+            font_size: 10.0,
+            color: powerup::color(effect.power_up),
+          },
+        )
+      })
+      .collect();
+    let unchanged = sections.len() == text.sections.len()
+      && sections
+        .iter()
+        .zip(text.sections.iter())
+        .all(|(new, old)| new.value == old.value);
+    if !unchanged {
+      text.sections = sections;
     }
   }
 }
 
-pub fn static_ui_texts(mut commands: Commands, asset_server: Res<AssetServer>) {
-  commands.spawn(
-    TextBundle::from_section(
-      "Cookie Crab",
-      TextStyle {
-        font: asset_server.load("OverpassMono-SemiBold.ttf"),
-        font_size: 10.0,
-        color: player::COLOR_P1,
-      },
-    )
-    .with_style(Style {
-      position_type: PositionType::Absolute,
-      top: Val::Px(10.0),
-      left: Val::Px(15.0),
-      ..default()
-    }),
-  );
-  commands.spawn(
-    TextBundle::from_section(
-      "Sid Starfish",
-      TextStyle {
-        font: asset_server.load("OverpassMono-SemiBold.ttf"),
-        font_size: 10.0,
-        color: player::COLOR_P2,
-      },
-    )
-    .with_style(Style {
-      position_type: PositionType::Absolute,
-      top: Val::Px(10.0),
-      right: Val::Px(15.0),
-      ..default()
-    }),
-  );
-  commands.spawn(
-    TextBundle::from_section(
-      "Foo Frog",
-      TextStyle {
-        font: asset_server.load("OverpassMono-SemiBold.ttf"),
-        font_size: 10.0,
-        color: player::COLOR_P3,
-      },
-    )
-    .with_style(Style {
-      position_type: PositionType::Absolute,
-      bottom: Val::Px(10.0),
-      left: Val::Px(15.0),
-      ..default()
-    }),
-  );
-  commands.spawn(
-    TextBundle::from_section(
-      "Jabby Jellyfish",
-      TextStyle {
-        font: asset_server.load("OverpassMono-SemiBold.ttf"),
-        font_size: 10.0,
-        color: player::COLOR_P4,
-      },
-    )
-    .with_style(Style {
-      position_type: PositionType::Absolute,
-      bottom: Val::Px(10.0),
-      right: Val::Px(15.0),
-      ..default()
-    }),
-  );
+/// One player's name and score in the HUD, hidden until they join
+#[derive(Component)]
+struct HudEntry(PlayerTag);
+
+fn hud_visibility(roster: Res<roster::Roster>, mut entries: Query<(&HudEntry, &mut Style)>) {
+  for (entry, mut style) in entries.iter_mut() {
+    let display = if roster.get(entry.0).joined {
+      Display::Flex
+    } else {
+      Display::None
+    };
+    if style.display != display {
+      style.display = display;
+    }
+  }
 }
 
-pub fn test_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
+/// Lays out a name and score for every roster slot: the first half along the top
+/// edge and the rest along the bottom, spread evenly over the width
+pub fn hud_setup(
+  mut commands: Commands,
+  fonts: Res<init::FontAssets>,
+  roster: Res<roster::Roster>,
+) {
+  let tags: Vec<PlayerTag> = roster.tags().collect();
+  let (top, bottom) = tags.split_at(tags.len().div_ceil(2));
   commands
     .spawn((
       NodeBundle {
         style: Style {
-          display: Display::Grid,
-          grid_template_columns: RepeatedGridTrack::flex(2, 1.0),
-          grid_template_rows: RepeatedGridTrack::flex(2, 1.0),
           width: Val::Percent(100.0),
           height: Val::Percent(100.0),
-          align_content: AlignContent::SpaceBetween,
+          flex_direction: FlexDirection::Column,
+          justify_content: JustifyContent::SpaceBetween,
+          padding: UiRect::axes(Val::Px(15.0), Val::Px(10.0)),
           ..default()
         },
-        //background_color: Color::rgb(0.15, 0.15, 0.15).into(),
         ..default()
       },
       OnGame,
     ))
     .with_children(|parent| {
-      parent.spawn((
-        TextBundle::from_section(
-          "1",
-          TextStyle {
-            font: asset_server.load("OverpassMono-SemiBold.ttf"),
-            font_size: 30.0,
-            color: player::COLOR_P1,
-          },
-        )
-        .with_style(Style {
-          position_type: PositionType::Absolute,
-          top: Val::Px(25.0),
-          left: Val::Px(15.0),
-          ..default()
-        }),
-        // Because this is a distinct label widget and
-        // not button/list item text, this is necessary
-        // for accessibility to treat the text accordingly.
-        PlayerTag::Player1,
-      ));
-      parent.spawn((
-        TextBundle::from_section(
-          "2",
-          TextStyle {
-            font: asset_server.load("OverpassMono-SemiBold.ttf"),
-            font_size: 30.0,
-            color: player::COLOR_P2,
-          },
-        )
-        .with_style(Style {
-          position_type: PositionType::Absolute,
-          top: Val::Px(25.0),
-          right: Val::Px(15.0),
-          ..default()
-        }),
-        PlayerTag::Player2,
-      ));
-      parent.spawn((
-        TextBundle::from_section(
-          "3",
-          TextStyle {
-            font: asset_server.load("OverpassMono-SemiBold.ttf"),
-            font_size: 30.0,
-            color: player::COLOR_P3,
-          },
-        )
-        .with_style(Style {
-          position_type: PositionType::Absolute,
-          bottom: Val::Px(25.0),
-          left: Val::Px(15.0),
-          ..default()
-        }),
-        PlayerTag::Player3,
-      ));
-      parent.spawn((
-        TextBundle::from_section(
-          "4",
-          TextStyle {
-            font: asset_server.load("OverpassMono-SemiBold.ttf"),
-            font_size: 30.0,
-            color: player::COLOR_P4,
-          },
-        )
-        .with_style(Style {
-          position_type: PositionType::Absolute,
-          bottom: Val::Px(25.0),
-          right: Val::Px(15.0),
-          ..default()
-        }),
-        PlayerTag::Player4,
-      ));
+      for (row, direction) in [
+        (top, FlexDirection::Column),
+        (bottom, FlexDirection::ColumnReverse),
+      ] {
+        parent
+          .spawn(NodeBundle {
+            style: Style {
+              width: Val::Percent(100.0),
+              justify_content: JustifyContent::SpaceBetween,
+              ..default()
+            },
+            ..default()
+          })
+          .with_children(|parent| {
+            for tag in row {
+              let slot = roster.get(*tag);
+              parent
+                .spawn((
+                  NodeBundle {
+                    style: Style {
+                      flex_direction: direction,
+                      align_items: AlignItems::Center,
+                      display: Display::None,
+                      ..default()
+                    },
+                    ..default()
+                  },
+                  HudEntry(*tag),
+                ))
+                .with_children(|parent| {
+                  parent.spawn(TextBundle::from_section(
+                    slot.name.clone(),
+                    TextStyle {
+                      font: fonts.overpass.clone(),
+                      font_size: 10.0,
+                      color: slot.color,
+                    },
+                  ));
+                  parent.spawn((
+                    TextBundle::from_section(
+                      "0",
+                      TextStyle {
+                        font: fonts.overpass.clone(),
+                        font_size: 30.0,
+                        color: slot.color,
+                      },
+                    ),
+                    *tag,
+                  ));
+                  parent.spawn((TextBundle::default(), EffectsText(*tag)));
+                });
+            }
+          });
+      }
     });
 }
@@ -0,0 +1,139 @@
+use bevy::prelude::*;
+use std::cmp::Reverse;
+
+use super::despawn_screen;
+use crate::{init, mainmenu, roster, state};
+
+pub struct GameOverPlugin;
+
+/// This plugin shows the final standings once a match is decided
+impl Plugin for GameOverPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .add_state::<state::AppState>()
+      .add_systems(OnEnter(state::AppState::GameOver), gameover_setup)
+      .add_systems(
+        Update,
+        (mainmenu::button_colors, gameover_buttons).run_if(in_state(state::AppState::GameOver)),
+      )
+      .add_systems(
+        OnExit(state::AppState::GameOver),
+        despawn_screen::<OnGameOver>,
+      );
+  }
+}
+
+// Tag component used to tag entities added on the game over screen
+#[derive(Component)]
+struct OnGameOver;
+
+#[derive(Component)]
+enum GameOverButton {
+  Rematch,
+  MainMenu,
+}
+
+fn gameover_buttons(
+  interaction_query: Query<(&Interaction, &GameOverButton), (Changed<Interaction>, With<Button>)>,
+  mut next_state: ResMut<NextState<state::AppState>>,
+) {
+  for (interaction, button) in &interaction_query {
+    if *interaction != Interaction::Pressed {
+      continue;
+    }
+    match button {
+      GameOverButton::Rematch => next_state.set(state::AppState::Game),
+      GameOverButton::MainMenu => next_state.set(state::AppState::MainMenu),
+    }
+  }
+}
+
+/// Joined players ordered by what decides the match in the chosen format,
+/// with the other number as the tie breaker
+fn standings<'a>(
+  settings: &state::MatchSettings,
+  roster: &'a roster::Roster,
+) -> Vec<&'a roster::PlayerSlot> {
+  let mut rows: Vec<_> = roster.joined().map(|(_, slot)| slot).collect();
+  match settings.format {
+    state::MatchFormat::FirstToPoints(_) => {
+      rows.sort_by_key(|slot| (Reverse(slot.score), Reverse(slot.round_wins)))
+    }
+    state::MatchFormat::BestOfRounds(_) => {
+      rows.sort_by_key(|slot| (Reverse(slot.round_wins), Reverse(slot.score)))
+    }
+  }
+  rows
+}
+
+fn gameover_setup(
+  mut commands: Commands,
+  fonts: Res<init::FontAssets>,
+  settings: Res<state::MatchSettings>,
+  in_game_state: Res<state::InGameState>,
+  roster: Res<roster::Roster>,
+) {
+  let row_style = |color| TextStyle {
+    font: fonts.overpass.clone(),
+    font_size: 20.0,
+    color,
+  };
+  commands
+    .spawn((
+      NodeBundle {
+        style: Style {
+          width: Val::Percent(100.0),
+          align_items: AlignItems::Center,
+          justify_content: JustifyContent::Center,
+          flex_direction: FlexDirection::Column,
+          ..default()
+        },
+        ..default()
+      },
+      OnGameOver,
+    ))
+    .with_children(|parent| {
+      parent.spawn(
+        TextBundle::from_section(
+          format!("Game over after {} rounds", in_game_state.current_round),
+          TextStyle {
+            font: fonts.overpass.clone(),
+            font_size: 30.0,
+            color: Color::WHITE,
+          },
+        )
+        .with_style(Style {
+          margin: UiRect::bottom(Val::Px(15.0)),
+          ..default()
+        }),
+      );
+      parent.spawn(TextBundle::from_section(
+        format!("   {:<16}{:>7}{:>7}", "Player", "Rounds", "Points"),
+        row_style(Color::GRAY),
+      ));
+      for (place, slot) in standings(&settings, &roster).into_iter().enumerate() {
+        parent.spawn(TextBundle::from_section(
+          format!(
+            "{}. {:<16}{:>7}{:>7}",
+            place + 1,
+            slot.name,
+            slot.round_wins,
+            slot.score
+          ),
+          row_style(slot.color),
+        ));
+      }
+      parent
+        .spawn(NodeBundle {
+          style: Style {
+            margin: UiRect::top(Val::Px(20.0)),
+            ..default()
+          },
+          ..default()
+        })
+        .with_children(|parent| {
+          mainmenu::spawn_button(parent, &fonts, "Rematch", GameOverButton::Rematch);
+          mainmenu::spawn_button(parent, &fonts, "Main menu", GameOverButton::MainMenu);
+        });
+    });
+}
@@ -1,14 +1,13 @@
 use bevy::prelude::*;
 use bevy_asset_loader::prelude::*;
 
-use crate::state;
+use crate::{levels, roster, state};
 
 pub struct InitPlugin;
 
 /// This plugin loads all assets using [`AssetLoader`] from a third party bevy plugin
 /// Alternatively you can write the logic to load assets yourself
 /// If interested, take a look at <https://bevy-cheatbook.github.io/features/assets.html>
-
 impl Plugin for InitPlugin {
   fn build(&self, app: &mut App) {
     app
@@ -18,24 +17,14 @@ impl Plugin for InitPlugin {
       )
       .add_collection_to_loading_state::<_, FontAssets>(state::AppState::Loading)
       .add_collection_to_loading_state::<_, ImageAssets>(state::AppState::Loading)
-      .add_collection_to_loading_state::<_, TextureAssets>(state::AppState::Loading);
+      .add_collection_to_loading_state::<_, TextureAssets>(state::AppState::Loading)
+      .add_systems(
+        OnExit(state::AppState::Loading),
+        (roster::setup_roster, levels::load_levels),
+      );
   }
 }
 
-#[derive(Resource)]
-pub struct Materials {
-  pub head_material: Handle<ColorMaterial>,
-  pub segment_material: Handle<ColorMaterial>,
-}
-
-#[derive(Resource)]
-pub struct TempTextureAssets {
-  pub crab: usize,
-  pub starfish: usize,
-  pub frog: usize,
-  pub jellyfish: usize,
-}
-
 #[derive(AssetCollection, Resource)]
 pub struct FontAssets {
   #[asset(path = "OverpassMono-SemiBold.ttf")]
@@ -58,4 +47,4 @@ pub struct TextureAssets {
   pub frog: Handle<Image>,
   #[asset(path = "jellyfish.png")]
   pub jellyfish: Handle<Image>,
-}
\ No newline at end of file
+}
@@ -0,0 +1,44 @@
+use bevy::prelude::*;
+
+use crate::sim;
+
+/// Folder with the level files, relative to the working directory like the other assets
+const LEVELS_DIR: &str = "assets/levels";
+
+/// Every level that can be picked in the lobby. The first one is always the empty
+/// arena, followed by the files in `assets/levels/` sorted by file name.
+#[derive(Resource)]
+pub struct Levels(pub Vec<sim::Level>);
+
+impl Levels {
+  /// The level at `index`, counting around so a stale index still picks one
+  pub fn get(&self, index: usize) -> &sim::Level {
+    &self.0[index % self.0.len()]
+  }
+}
+
+/// Reads the level files. A file that doesn't parse is skipped with a warning.
+pub fn load_levels(mut commands: Commands) {
+  let mut levels = vec![sim::Level {
+    name: "Open arena".to_string(),
+    ..default()
+  }];
+  let mut paths: Vec<_> = std::fs::read_dir(LEVELS_DIR)
+    .into_iter()
+    .flatten()
+    .flatten()
+    .map(|entry| entry.path())
+    .filter(|path| path.extension().is_some_and(|extension| extension == "ron"))
+    .collect();
+  paths.sort();
+  for path in paths {
+    let level = std::fs::read_to_string(&path)
+      .map_err(|error| error.to_string())
+      .and_then(|level| ron::from_str::<sim::Level>(&level).map_err(|error| error.to_string()));
+    match level {
+      Ok(level) => levels.push(level),
+      Err(error) => warn!("Ignoring level {}: {}", path.display(), error),
+    }
+  }
+  commands.insert_resource(Levels(levels));
+}
@@ -0,0 +1,9 @@
+//! The headless part of the game: rules, scoring and computer players. The game
+//! draws it with Bevy; the tournament runner plays bots against each other without
+//! opening a window.
+
+pub mod ai;
+pub mod bot;
+pub mod external;
+pub mod scoring;
+pub mod sim;
@@ -0,0 +1,191 @@
+use bevy::prelude::*;
+
+use super::despawn_screen;
+use crate::{game, init, levels, mainmenu, player, roster, state};
+
+pub struct LobbyPlugin;
+
+/// This plugin shows the match setup screen between the main menu and the game
+impl Plugin for LobbyPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .add_state::<state::AppState>()
+      .init_resource::<state::MatchSettings>()
+      .add_systems(
+        OnEnter(state::AppState::Lobby),
+        (lobby_setup, clear_players),
+      )
+      .add_systems(
+        Update,
+        (mainmenu::button_colors, lobby_buttons, option_labels)
+          .chain()
+          .run_if(in_state(state::AppState::Lobby)),
+      )
+      .add_systems(OnExit(state::AppState::Lobby), despawn_screen::<OnLobby>);
+  }
+}
+
+const OPTION_BUTTON: Vec2 = Vec2::new(200.0, 28.0);
+const CONTROLLER_BUTTON: Vec2 = Vec2::new(200.0, 24.0);
+
+// Tag component used to tag entities added on the lobby screen
+#[derive(Component)]
+struct OnLobby;
+
+#[derive(Component, Clone, Copy)]
+enum LobbyButton {
+  Format,
+  Scoring,
+  TrailMode,
+  PowerUps,
+  WrapAround,
+  Level,
+  Controller(game::PlayerTag),
+  Play,
+  Back,
+}
+
+/// A new match starts with only the bots joined and all gamepads unassigned
+fn clear_players(mut roster: ResMut<roster::Roster>) {
+  for slot in roster.slots.iter_mut() {
+    slot.joined = slot.bot.is_some();
+    slot.gamepad = None;
+  }
+}
+
+fn lobby_buttons(
+  interaction_query: Query<(&Interaction, &LobbyButton), (Changed<Interaction>, With<Button>)>,
+  mut settings: ResMut<state::MatchSettings>,
+  mut roster: ResMut<roster::Roster>,
+  levels: Res<levels::Levels>,
+  bots: Res<player::BotRegistry>,
+  mut next_state: ResMut<NextState<state::AppState>>,
+) {
+  for (interaction, button) in &interaction_query {
+    if *interaction != Interaction::Pressed {
+      continue;
+    }
+    match button {
+      LobbyButton::Format => settings.format = settings.format.next(),
+      LobbyButton::Scoring => settings.scoring = settings.scoring.next(),
+      LobbyButton::TrailMode => settings.trail_mode = settings.trail_mode.next(),
+      LobbyButton::PowerUps => settings.power_ups = !settings.power_ups,
+      LobbyButton::WrapAround => settings.wrap_around = !settings.wrap_around,
+      LobbyButton::Level => settings.level = (settings.level + 1) % levels.0.len(),
+      LobbyButton::Controller(tag) => roster.get_mut(*tag).next_controller(&bots),
+      LobbyButton::Play => next_state.set(state::AppState::Game),
+      LobbyButton::Back => next_state.set(state::AppState::MainMenu),
+    }
+  }
+}
+
+/// Keeps the option buttons' text in sync with the current settings
+fn option_labels(
+  settings: Res<state::MatchSettings>,
+  roster: Res<roster::Roster>,
+  levels: Res<levels::Levels>,
+  buttons: Query<(&LobbyButton, &Children)>,
+  mut text_query: Query<&mut Text>,
+) {
+  for (button, children) in &buttons {
+    let value = match button {
+      LobbyButton::Format => settings.format.label(),
+      LobbyButton::Scoring => settings.scoring.label(),
+      LobbyButton::TrailMode => settings.trail_mode.label(),
+      LobbyButton::PowerUps => match settings.power_ups {
+        true => "Power-ups on".to_string(),
+        false => "Power-ups off".to_string(),
+      },
+      LobbyButton::WrapAround => match settings.wrap_around {
+        true => "Wrap-around edges".to_string(),
+        false => "Deadly walls".to_string(),
+      },
+      LobbyButton::Level => format!("Level: {}", levels.get(settings.level).name),
+      LobbyButton::Controller(tag) => {
+        let slot = roster.get(*tag);
+        format!("{}: {}", slot.name, slot.controller_label())
+      }
+      LobbyButton::Play | LobbyButton::Back => continue,
+    };
+    let mut text = text_query.get_mut(children[0]).unwrap();
+    if text.sections[0].value != value {
+      text.sections[0].value = value;
+    }
+  }
+}
+
+/// A row of buttons that continues on the next line when the window is full
+fn wrapping_row() -> NodeBundle {
+  NodeBundle {
+    style: Style {
+      width: Val::Percent(100.0),
+      flex_wrap: FlexWrap::Wrap,
+      justify_content: JustifyContent::Center,
+      margin: UiRect::bottom(Val::Px(5.0)),
+      ..default()
+    },
+    ..default()
+  }
+}
+
+fn lobby_setup(mut commands: Commands, fonts: Res<init::FontAssets>, roster: Res<roster::Roster>) {
+  commands
+    .spawn((
+      NodeBundle {
+        style: Style {
+          width: Val::Percent(100.0),
+          align_items: AlignItems::Center,
+          justify_content: JustifyContent::Center,
+          flex_direction: FlexDirection::Column,
+          ..default()
+        },
+        ..default()
+      },
+      OnLobby,
+    ))
+    .with_children(|parent| {
+      parent.spawn(
+        TextBundle::from_section(
+          "Match setup",
+          TextStyle {
+            font: fonts.overpass.clone(),
+            font_size: 30.0,
+            color: Color::WHITE,
+          },
+        )
+        .with_style(Style {
+          margin: UiRect::bottom(Val::Px(10.0)),
+          ..default()
+        }),
+      );
+      // Match options, then who steers every player
+      parent.spawn(wrapping_row()).with_children(|parent| {
+        for option in [
+          LobbyButton::Format,
+          LobbyButton::Scoring,
+          LobbyButton::TrailMode,
+          LobbyButton::PowerUps,
+          LobbyButton::WrapAround,
+          LobbyButton::Level,
+        ] {
+          mainmenu::spawn_sized_button(parent, &fonts, "", OPTION_BUTTON, 16.0, option);
+        }
+      });
+      parent.spawn(wrapping_row()).with_children(|parent| {
+        for tag in roster.tags() {
+          mainmenu::spawn_sized_button(
+            parent,
+            &fonts,
+            "",
+            CONTROLLER_BUTTON,
+            14.0,
+            LobbyButton::Controller(tag),
+          );
+        }
+      });
+      parent.spawn(NodeBundle::default()).with_children(|parent| {
+        mainmenu::spawn_button(parent, &fonts, "Back", LobbyButton::Back);
+        mainmenu::spawn_button(parent, &fonts, "Play", LobbyButton::Play);
+      });
+    });
+}
@@ -1,11 +1,25 @@
+#![allow(clippy::type_complexity, clippy::too_many_arguments)]
+
 use bevy::{prelude::*, window::PresentMode};
+use furious_purpose::{bot, external, scoring, sim};
 
+mod controls;
+mod editor;
+mod events;
 mod game;
+mod gameover;
 mod init;
+mod levels;
+mod lobby;
 mod mainmenu;
+mod pause;
 mod player;
+mod powerup;
+mod roster;
+mod round;
 mod splash;
 mod state;
+mod trail;
 
 pub fn setup(mut commands: Commands, mut next_state: ResMut<NextState<state::AppState>>) {
   commands.spawn(Camera2dBundle::default());
@@ -25,15 +39,24 @@ fn main() {
     }))
     .insert_resource(ClearColor(Color::rgb(0.0, 0.0, 0.0)))
     .insert_resource(state::InGameState::default())
-    .insert_resource(state::PlayerScores::default())
-    .insert_resource(player::PlayerSegments::default())
+    .insert_resource(player::Simulation::default())
+    .insert_resource(player::PlayerTurns::default())
     .add_systems(Update, toggle_vsync)
     .add_state::<state::AppState>()
     .add_plugins(init::InitPlugin)
+    .add_plugins(events::EventsPlugin)
     .add_plugins(splash::SplashPlugin)
     .add_plugins(mainmenu::MainMenuPlugin)
+    .add_plugins(controls::ControlsPlugin)
+    .add_plugins(editor::EditorPlugin)
+    .add_plugins(lobby::LobbyPlugin)
     .add_plugins(game::GamePlugin)
     .add_plugins(player::PlayerPlugin)
+    .add_plugins(trail::TrailPlugin)
+    .add_plugins(powerup::PowerUpPlugin)
+    .add_plugins(round::RoundPlugin)
+    .add_plugins(gameover::GameOverPlugin)
+    .add_plugins(pause::PausePlugin)
     .add_systems(Startup, setup)
     .run();
 }
@@ -1,13 +1,13 @@
 use bevy::prelude::*;
 
 use super::despawn_screen;
-use crate::state;
+use crate::{controls, init, roster, state};
 
 pub struct MainMenuPlugin;
 
-const NORMAL_BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
-const HOVERED_BUTTON: Color = Color::rgb(0.25, 0.25, 0.25);
-const PRESSED_BUTTON: Color = Color::rgb(0.35, 0.75, 0.35);
+pub const NORMAL_BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
+pub const HOVERED_BUTTON: Color = Color::rgb(0.25, 0.25, 0.25);
+pub const PRESSED_BUTTON: Color = Color::rgb(0.35, 0.75, 0.35);
 
 impl Plugin for MainMenuPlugin {
   fn build(&self, app: &mut App) {
@@ -16,7 +16,7 @@ impl Plugin for MainMenuPlugin {
       .add_systems(OnEnter(state::AppState::MainMenu), ui_setup)
       .add_systems(
         Update,
-        (button_system).run_if(in_state(state::AppState::MainMenu)),
+        (button_system, button_colors, menu_buttons).run_if(in_state(state::AppState::MainMenu)),
       )
       .add_systems(
         OnExit(state::AppState::MainMenu),
@@ -29,10 +29,20 @@ impl Plugin for MainMenuPlugin {
 #[derive(Component)]
 struct OnMainMenu;
 
+// The big start button has its own animated label
+#[derive(Component)]
+pub struct StartButton;
+
+#[derive(Component)]
+enum MenuButton {
+  Controls,
+  Editor,
+}
+
 pub fn button_system(
   mut interaction_query: Query<
     (&Interaction, &mut BackgroundColor, &Children),
-    (Changed<Interaction>, With<Button>),
+    (Changed<Interaction>, With<StartButton>),
   >,
   mut text_query: Query<&mut Text>,
   mut next_state: ResMut<NextState<state::AppState>>,
@@ -43,7 +53,7 @@ pub fn button_system(
       Interaction::Pressed => {
         text.sections[0].value = "^ - ^".to_string();
         *color = PRESSED_BUTTON.into();
-        next_state.set(state::AppState::Game);
+        next_state.set(state::AppState::Lobby);
       }
       Interaction::Hovered => {
         text.sections[0].value = "Start".to_string();
@@ -57,7 +67,42 @@ pub fn button_system(
   }
 }
 
-pub fn ui_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn menu_buttons(
+  interaction_query: Query<(&Interaction, &MenuButton), (Changed<Interaction>, With<Button>)>,
+  mut next_state: ResMut<NextState<state::AppState>>,
+) {
+  for (interaction, button) in &interaction_query {
+    if *interaction != Interaction::Pressed {
+      continue;
+    }
+    match button {
+      MenuButton::Controls => next_state.set(state::AppState::Controls),
+      MenuButton::Editor => next_state.set(state::AppState::Editor),
+    }
+  }
+}
+
+/// One line per player slot with their current keys
+fn controls_help(roster: &roster::Roster) -> String {
+  let mut help: String = roster
+    .slots
+    .iter()
+    .map(|slot| {
+      let keys = match slot.scheme {
+        roster::ControlScheme::Relative => vec![slot.keys.left, slot.keys.right],
+        roster::ControlScheme::Absolute => slot.keys.all().to_vec(),
+      };
+      let names: Vec<String> = keys.into_iter().map(controls::key_name).collect();
+      let (last, rest) = names.split_last().unwrap();
+      format!("{}: {} and {}\n", slot.name, rest.join(", "), last)
+    })
+    .collect();
+  help.push_str("Gamepads: A to join, bumpers or d-pad to turn\n");
+  help.push_str("\nPress ESC or Start to pause. Have fun!\n");
+  help
+}
+
+pub fn ui_setup(mut commands: Commands, fonts: Res<init::FontAssets>, roster: Res<roster::Roster>) {
   commands
     .spawn((
       // These are not some mysterious double parentheses but a tuple
@@ -76,43 +121,114 @@ pub fn ui_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
     .with_children(|parent| {
       parent.spawn(
         TextBundle::from_section(
-          "Player 1: Q and W\nPlayer 2: B and N\nPlayer 3: O and P\nPlayer 4: ← and →\n\nPress ESC to quit. Have fun!\n",
+          controls_help(&roster),
           TextStyle {
-            font: asset_server.load("OverpassMono-SemiBold.ttf"),
-            font_size: 20.0,
+            font: fonts.overpass.clone(),
+            font_size: 14.0,
             color: Color::WHITE,
           },
         )
         .with_text_alignment(TextAlignment::Center)
         .with_style(Style {
           align_items: AlignItems::Center,
-          margin: UiRect::bottom(Val::Px(30.0)),
+          margin: UiRect::bottom(Val::Px(10.0)),
           ..default()
         }),
       );
       parent
-        .spawn(ButtonBundle {
-          style: Style {
-            width: Val::Px(150.0),
-            height: Val::Px(65.0),
-            // horizontally center child text
-            justify_content: JustifyContent::Center,
-            // vertically center child text
-            align_items: AlignItems::Center,
+        .spawn((
+          ButtonBundle {
+            style: Style {
+              width: Val::Px(150.0),
+              height: Val::Px(65.0),
+              // horizontally center child text
+              justify_content: JustifyContent::Center,
+              // vertically center child text
+              align_items: AlignItems::Center,
+              ..default()
+            },
+            background_color: NORMAL_BUTTON.into(),
             ..default()
           },
-          background_color: NORMAL_BUTTON.into(),
-          ..default()
-        })
+          StartButton,
+        ))
         .with_children(|parent| {
           parent.spawn(TextBundle::from_section(
             "Button",
             TextStyle {
-              font: asset_server.load("OverpassMono-SemiBold.ttf"),
+              font: fonts.overpass.clone(),
               font_size: 40.0,
               color: Color::rgb(0.9, 0.9, 0.9),
             },
           ));
         });
+      parent.spawn(NodeBundle::default()).with_children(|parent| {
+        spawn_button(parent, &fonts, "Controls", MenuButton::Controls);
+        spawn_button(parent, &fonts, "Level editor", MenuButton::Editor);
+      });
+    });
+}
+
+/// Hover and press feedback for the buttons on the other menu screens
+pub fn button_colors(
+  mut interaction_query: Query<
+    (&Interaction, &mut BackgroundColor),
+    (Changed<Interaction>, With<Button>, Without<StartButton>),
+  >,
+) {
+  for (interaction, mut color) in &mut interaction_query {
+    *color = match *interaction {
+      Interaction::Pressed => PRESSED_BUTTON.into(),
+      Interaction::Hovered => HOVERED_BUTTON.into(),
+      Interaction::None => NORMAL_BUTTON.into(),
+    };
+  }
+}
+
+/// Spawns a menu button with a text label. `action` is inserted on the button so the
+/// screen's own button system knows what was pressed.
+pub fn spawn_button(
+  parent: &mut ChildBuilder,
+  fonts: &init::FontAssets,
+  label: &str,
+  action: impl Component,
+) {
+  spawn_sized_button(parent, fonts, label, Vec2::new(250.0, 40.0), 20.0, action);
+}
+
+/// Like `spawn_button`, for screens that need to fit many smaller buttons
+pub fn spawn_sized_button(
+  parent: &mut ChildBuilder,
+  fonts: &init::FontAssets,
+  label: &str,
+  size: Vec2,
+  font_size: f32,
+  action: impl Component,
+) {
+  parent
+    .spawn((
+      ButtonBundle {
+        style: Style {
+          width: Val::Px(size.x),
+          height: Val::Px(size.y),
+          margin: UiRect::all(Val::Px(size.y / 8.0)),
+          justify_content: JustifyContent::Center,
+          align_items: AlignItems::Center,
+          ..default()
+        },
+        background_color: NORMAL_BUTTON.into(),
+        ..default()
+      },
+      action,
+    ))
+    .with_children(|parent| {
+      parent.spawn(TextBundle::from_section(
+        label,
+        TextStyle {
+          font: fonts.overpass.clone(),
+          font_size,
+          color: Color::rgb(0.9, 0.9, 0.9),
+        },
+      ));
     });
 }
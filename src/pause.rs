@@ -0,0 +1,115 @@
+use bevy::{app::AppExit, prelude::*};
+
+use super::despawn_screen;
+use crate::{game, init, mainmenu, player, roster, round, state};
+
+pub struct PausePlugin;
+
+/// This plugin shows the pause menu on top of the frozen arena. Nothing in the
+/// simulation runs while in `state::AppState::Paused`.
+impl Plugin for PausePlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .add_state::<state::AppState>()
+      .add_systems(OnEnter(state::AppState::Paused), pause_setup)
+      .add_systems(
+        Update,
+        (mainmenu::button_colors, pause_buttons, pause_input)
+          .run_if(in_state(state::AppState::Paused)),
+      )
+      .add_systems(OnExit(state::AppState::Paused), despawn_screen::<OnPause>);
+  }
+}
+
+// Tag component used to tag entities added on the pause overlay
+#[derive(Component)]
+struct OnPause;
+
+#[derive(Component)]
+enum PauseButton {
+  Resume,
+  RestartRound,
+  MainMenu,
+  Quit,
+}
+
+/// The same button that paused the game resumes it
+fn pause_input(
+  keyboard_input: Res<Input<KeyCode>>,
+  gamepads: Res<Gamepads>,
+  gamepad_input: Res<Input<GamepadButton>>,
+  mut next_state: ResMut<NextState<state::AppState>>,
+) {
+  if game::pause_pressed(&keyboard_input, &gamepads, &gamepad_input) {
+    next_state.set(state::AppState::Game);
+  }
+}
+
+fn pause_buttons(
+  interaction_query: Query<(&Interaction, &PauseButton), (Changed<Interaction>, With<Button>)>,
+  mut round: ResMut<round::Round>,
+  mut in_game_state: ResMut<state::InGameState>,
+  mut roster: ResMut<roster::Roster>,
+  mut simulation: ResMut<player::Simulation>,
+  mut next_state: ResMut<NextState<state::AppState>>,
+  mut exit: EventWriter<AppExit>,
+) {
+  for (interaction, button) in &interaction_query {
+    if *interaction != Interaction::Pressed {
+      continue;
+    }
+    match button {
+      PauseButton::Resume => next_state.set(state::AppState::Game),
+      PauseButton::RestartRound => {
+        round.restart(&mut in_game_state, &mut roster, &mut simulation);
+        next_state.set(state::AppState::Game);
+      }
+      PauseButton::MainMenu => next_state.set(state::AppState::MainMenu),
+      PauseButton::Quit => {
+        println!("Thanks for playing Aninmals Tron!");
+        exit.send(AppExit);
+      }
+    }
+  }
+}
+
+fn pause_setup(mut commands: Commands, fonts: Res<init::FontAssets>) {
+  commands
+    .spawn((
+      NodeBundle {
+        style: Style {
+          width: Val::Percent(100.0),
+          height: Val::Percent(100.0),
+          position_type: PositionType::Absolute,
+          align_items: AlignItems::Center,
+          justify_content: JustifyContent::Center,
+          flex_direction: FlexDirection::Column,
+          ..default()
+        },
+        background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+        z_index: ZIndex::Global(10),
+        ..default()
+      },
+      OnPause,
+    ))
+    .with_children(|parent| {
+      parent.spawn(
+        TextBundle::from_section(
+          "Paused",
+          TextStyle {
+            font: fonts.overpass.clone(),
+            font_size: 30.0,
+            color: Color::WHITE,
+          },
+        )
+        .with_style(Style {
+          margin: UiRect::bottom(Val::Px(15.0)),
+          ..default()
+        }),
+      );
+      mainmenu::spawn_button(parent, &fonts, "Resume", PauseButton::Resume);
+      mainmenu::spawn_button(parent, &fonts, "Restart round", PauseButton::RestartRound);
+      mainmenu::spawn_button(parent, &fonts, "Back to main menu", PauseButton::MainMenu);
+      mainmenu::spawn_button(parent, &fonts, "Quit", PauseButton::Quit);
+    });
+}
@@ -1,386 +1,464 @@
 use bevy::{
+  input::gamepad::GamepadConnectionEvent,
   prelude::*,
   prelude::{Input, KeyCode, Res},
 };
 
-use crate::{game, init, state};
-use rand::Rng;
-use std::collections::HashMap;
-
-pub const COLOR_P1: Color = Color::rgb(0.99, 0.45, 0.0);
-pub const COLOR_P2: Color = Color::rgb(0.99, 0.25, 0.20);
-pub const COLOR_P3: Color = Color::rgb(0.5, 0.95, 0.3);
-pub const COLOR_P4: Color = Color::rgb(0.3, 0.75, 0.99);
+use crate::{bot, events, external, game, roster, round, sim, state};
 
 pub struct PlayerPlugin;
 
-#[derive(Component, Debug, Clone)]
+#[derive(Component, Debug, Clone, Copy)]
 struct Player {
-  name: String,
   player_tag: game::PlayerTag,
 }
 
 #[derive(Component, Debug)]
-struct PlayerHead {
-  direction: Direction,
-}
+struct PlayerHead;
 
-#[derive(Component)]
-struct Name(String);
+/// The simulation driven by the systems in this plugin
+#[derive(Resource, Deref, DerefMut)]
+pub struct Simulation(pub sim::Simulation);
 
-#[derive(PartialEq, Copy, Clone, Debug)]
-enum Direction {
-  Left,
-  Up,
-  Right,
-  Down,
+impl Default for Simulation {
+  fn default() -> Self {
+    Simulation(sim::Simulation::new(roster::MAX_PLAYERS, rand::random()))
+  }
 }
 
-impl Direction {
-  fn turn_left(self) -> Self {
-    match self {
-      Self::Left => Self::Down,
-      Self::Right => Self::Up,
-      Self::Up => Self::Left,
-      Self::Down => Self::Right,
+/// The bots that can be picked in the lobby: the built-in ones followed by the
+/// external ones in `external::CONFIG_FILE`
+#[derive(Resource, Deref, DerefMut)]
+pub struct BotRegistry(pub bot::BotRegistry);
+
+impl Default for BotRegistry {
+  fn default() -> Self {
+    let mut registry = bot::BotRegistry::builtin();
+    match external::load_config(external::CONFIG_FILE) {
+      Ok(configs) => configs
+        .into_iter()
+        .for_each(|config| config.register(&mut registry)),
+      Err(error) => warn!("Ignoring external bots: {}", error),
     }
+    BotRegistry(registry)
   }
-  fn turn_right(self) -> Self {
-    match self {
-      Self::Left => Self::Up,
-      Self::Right => Self::Down,
-      Self::Up => Self::Right,
-      Self::Down => Self::Left,
+}
+
+/// How many turns each player can queue up ahead of the simulation. Presses beyond
+/// that are dropped until a tick has consumed one.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct InputBuffer(pub usize);
+
+impl Default for InputBuffer {
+  fn default() -> Self {
+    InputBuffer(3)
+  }
+}
+
+/// Turns pressed but not applied yet, oldest first, indexed by player slot.
+/// Input is read every frame and every fixed-rate tick consumes one turn per slot,
+/// so two quick presses between ticks become a U-turn over two ticks.
+#[derive(Resource, Default)]
+pub struct PlayerTurns([std::collections::VecDeque<sim::Turn>; roster::MAX_PLAYERS]);
+
+impl PlayerTurns {
+  pub fn push(&mut self, tag: game::PlayerTag, turn: sim::Turn, buffer: InputBuffer) {
+    let queue = &mut self.0[tag.index()];
+    if queue.len() < buffer.0 {
+      queue.push_back(turn);
     }
   }
+
+  /// Takes the oldest queued turn of every slot whose snake moves in the coming tick.
+  /// A slowed snake sitting out the tick keeps its turns queued.
+  fn next_tick(
+    &mut self,
+    simulation: &sim::Simulation,
+  ) -> [Option<sim::Turn>; roster::MAX_PLAYERS] {
+    std::array::from_fn(|slot| match simulation.sits_out(slot) {
+      true => None,
+      false => self.0[slot].pop_front(),
+    })
+  }
+
+  pub fn clear(&mut self) {
+    self.0.iter_mut().for_each(|queue| queue.clear());
+  }
 }
 
+/// The bot steering each slot in the current match, indexed by slot
 #[derive(Resource, Default)]
-pub struct PlayerSegments(pub HashMap<String, Vec<Entity>>);
+pub struct SlotBots(Vec<Option<Box<dyn bot::Bot>>>);
+
+impl SlotBots {
+  /// Lets every bot know a new round starts
+  pub fn new_round(&mut self) {
+    for bot in self.0.iter_mut().flatten() {
+      bot.new_round();
+    }
+  }
+}
+
+/// The fixed-rate system stepping the simulation. Systems reading the events of a
+/// tick while the simulation still shows it run after this set.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SimulationStep;
 
 /// This plugin handles player related stuff like movement
 /// Player logic is only active during the State `state::AppState::Game`
 impl Plugin for PlayerPlugin {
   fn build(&self, app: &mut App) {
-    app.add_state::<state::AppState>().add_systems(
-      Update,
-      (
-        player_spawn_input,
-        player_movement_input,
-        move_players,
-        grow_player_tails,
+    app
+      .add_state::<state::AppState>()
+      .init_resource::<InputBuffer>()
+      .init_resource::<SlotBots>()
+      .init_resource::<BotRegistry>()
+      .add_systems(
+        Update,
+        (
+          player_join_input,
+          player_movement_input.run_if(round::playing),
+          gamepad_movement_input.run_if(round::playing),
+          sync_heads,
+        )
+          .run_if(in_state(state::AppState::Game)),
       )
-        .run_if(in_state(state::AppState::Game)),
-    );
+      // A lost gamepad pauses the game, so it has to be reclaimable from the pause menu
+      .add_systems(
+        Update,
+        gamepad_join_input
+          .run_if(in_state(state::AppState::Game).or_else(in_state(state::AppState::Paused))),
+      )
+      .add_systems(Update, gamepad_disconnect)
+      .add_systems(
+        FixedUpdate,
+        (bot_input, move_players.in_set(SimulationStep))
+          .chain()
+          .run_if(in_state(state::AppState::Game).and_then(round::playing)),
+      );
+    for transition in state::MATCH_START {
+      app.add_systems(transition, create_bots);
+    }
+    for match_end in state::MATCH_END {
+      app.add_systems(OnEnter(match_end), despawn_players);
+    }
   }
 }
 
-fn player_spawn_input(
-  keyboard_input: Res<Input<KeyCode>>,
-  mut in_game_state: ResMut<state::InGameState>,
-  mut commands: Commands,
-  textures: Res<init::TextureAssets>,
-) {
-  let mut rng = rand::thread_rng();
-  if !in_game_state.player1 && keyboard_input.any_just_pressed([KeyCode::Q, KeyCode::W]) {
-    let start_position = game::Position {
-      x: rng.gen_range(100..540),
-      y: rng.gen_range(80..280),
-    };
-    spawn_player(
-      &mut commands,
-      &textures,
-      "Cookie Crab",
-      start_position,
-      Direction::Down,
-    );
-    in_game_state.player1 = true;
+/// Pressing any of your keys joins the next round
+fn player_join_input(keyboard_input: Res<Input<KeyCode>>, mut roster: ResMut<roster::Roster>) {
+  for slot in roster.slots.iter_mut() {
+    if keyboard_input.any_just_pressed(slot.keys.all()) {
+      slot.joined = true;
+    }
   }
-  if !in_game_state.player2 && keyboard_input.any_just_pressed([KeyCode::B, KeyCode::N]) {
-    let start_position = game::Position {
-      x: rng.gen_range(100..540),
-      y: rng.gen_range(80..280),
+}
+
+/// Pressing A on a gamepad that isn't playing yet joins it into a slot. A slot whose
+/// gamepad went away is picked up again first, otherwise the first free slot is taken.
+fn gamepad_join_input(
+  gamepads: Res<Gamepads>,
+  gamepad_input: Res<Input<GamepadButton>>,
+  mut roster: ResMut<roster::Roster>,
+) {
+  for gamepad in gamepads.iter() {
+    if !gamepad_input.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::South)) {
+      continue;
+    }
+    let assigned = |slot: &roster::PlayerSlot| {
+      slot
+        .gamepad
+        .is_some_and(|assigned| gamepads.contains(assigned))
     };
-    spawn_player(
-      &mut commands,
-      &textures,
-      "Sid Starfish",
-      start_position,
-      Direction::Down,
-    );
-    in_game_state.player2 = true;
-  }
-  if !in_game_state.player3 && keyboard_input.any_just_pressed([KeyCode::O, KeyCode::P]) {
-    let start_position = game::Position {
-      x: rng.gen_range(100..540),
-      y: rng.gen_range(80..280),
+    if roster
+      .slots
+      .iter()
+      .any(|slot| assigned(slot) && slot.gamepad == Some(gamepad))
+    {
+      continue;
+    }
+    let orphaned = roster
+      .slots
+      .iter()
+      .position(|slot| slot.joined && slot.gamepad.is_some() && !assigned(slot));
+    let free = || {
+      roster
+        .slots
+        .iter()
+        .position(|slot| !slot.joined && slot.gamepad.is_none())
     };
-    spawn_player(
-      &mut commands,
-      &textures,
-      "Foo Frog",
-      start_position,
-      Direction::Down,
-    );
-    in_game_state.player3 = true;
+    if let Some(index) = orphaned.or_else(free) {
+      let slot = &mut roster.slots[index];
+      slot.gamepad = Some(gamepad);
+      slot.joined = true;
+    }
   }
-  if !in_game_state.player4 && keyboard_input.any_just_pressed([KeyCode::Left, KeyCode::Right]) {
-    let start_position = game::Position {
-      x: rng.gen_range(100..540),
-      y: rng.gen_range(80..280),
+}
+
+/// A gamepad disconnecting while its player is in the match pauses the game, so
+/// they can plug it back in and press A to take their slot again
+fn gamepad_disconnect(
+  mut connections: EventReader<GamepadConnectionEvent>,
+  roster: Res<roster::Roster>,
+  app_state: Res<State<state::AppState>>,
+  mut next_state: ResMut<NextState<state::AppState>>,
+) {
+  for connection in connections.iter() {
+    if connection.connected() {
+      continue;
+    }
+    let Some(slot) = roster
+      .slots
+      .iter()
+      .find(|slot| slot.joined && slot.gamepad == Some(connection.gamepad))
+    else {
+      continue;
     };
-    spawn_player(
-      &mut commands,
-      &textures,
-      "Jabby Jellyfish",
-      start_position,
-      Direction::Down,
-    );
-    in_game_state.player4 = true;
+    info!("{} lost their gamepad", slot.name);
+    if *app_state.get() == state::AppState::Game {
+      next_state.set(state::AppState::Paused);
+    }
   }
 }
 
 fn player_movement_input(
   keyboard_input: Res<Input<KeyCode>>,
-  mut heads: Query<(&mut PlayerHead, &Player)>,
+  roster: Res<roster::Roster>,
+  heads: Query<&Player, With<PlayerHead>>,
+  buffer: Res<InputBuffer>,
+  mut turns: ResMut<PlayerTurns>,
 ) {
-  for (mut head, player) in heads.iter_mut() {
-    let dir: Direction = if player.name == "Cookie Crab" {
-      if keyboard_input.just_pressed(KeyCode::Q) {
-        Direction::turn_left(head.direction)
-      } else if keyboard_input.just_pressed(KeyCode::W) {
-        Direction::turn_right(head.direction)
-      } else {
-        head.direction
-      }
-    } else if player.name == "Sid Starfish" {
-      if keyboard_input.just_pressed(KeyCode::B) {
-        Direction::turn_left(head.direction)
-      } else if keyboard_input.just_pressed(KeyCode::N) {
-        Direction::turn_right(head.direction)
-      } else {
-        head.direction
-      }
-    } else if player.name == "Foo Frog" {
-      if keyboard_input.just_pressed(KeyCode::O) {
-        Direction::turn_left(head.direction)
-      } else if keyboard_input.just_pressed(KeyCode::P) {
-        Direction::turn_right(head.direction)
-      } else {
-        head.direction
-      }
-    } else if player.name == "Jabby Jellyfish" {
-      if keyboard_input.just_pressed(KeyCode::Left) {
-        Direction::turn_left(head.direction)
-      } else if keyboard_input.just_pressed(KeyCode::Right) {
-        Direction::turn_right(head.direction)
-      } else {
-        head.direction
-      }
-    } else {
-      head.direction
-    };
-    head.direction = dir;
+  for player in heads.iter() {
+    let slot = roster.get(player.player_tag);
+    if slot.bot.is_some() {
+      continue;
+    }
+    let keys = slot.keys;
+    let turn = steering(
+      slot.scheme,
+      |key| keyboard_input.just_pressed(key),
+      keys.all(),
+    );
+    if let Some(turn) = turn {
+      turns.push(player.player_tag, turn, *buffer);
+    }
   }
 }
 
-fn spawn_player(
-  commands: &mut Commands,
-  textures: &Res<init::TextureAssets>,
-  player_name: &str,
-  start_position: game::Position,
-  direction: Direction,
+/// Maps the first pressed of the left, right, up and down inputs to a turn. The
+/// relative scheme only listens to left and right.
+fn steering<T: Copy>(
+  scheme: roster::ControlScheme,
+  just_pressed: impl Fn(T) -> bool,
+  [left, right, up, down]: [T; 4],
+) -> Option<sim::Turn> {
+  match scheme {
+    roster::ControlScheme::Relative => [(left, sim::Turn::Left), (right, sim::Turn::Right)]
+      .into_iter()
+      .find(|(input, _)| just_pressed(*input)),
+    roster::ControlScheme::Absolute => [
+      (left, sim::Turn::Face(sim::Direction::Left)),
+      (right, sim::Turn::Face(sim::Direction::Right)),
+      (up, sim::Turn::Face(sim::Direction::Up)),
+      (down, sim::Turn::Face(sim::Direction::Down)),
+    ]
+    .into_iter()
+    .find(|(input, _)| just_pressed(*input)),
+  }
+  .map(|(_, turn)| turn)
+}
+
+/// The d-pad steers the snake of the slot a gamepad joined into, following the
+/// slot's control scheme. Bumpers always turn relative to the heading.
+fn gamepad_movement_input(
+  gamepad_input: Res<Input<GamepadButton>>,
+  roster: Res<roster::Roster>,
+  heads: Query<&Player, With<PlayerHead>>,
+  buffer: Res<InputBuffer>,
+  mut turns: ResMut<PlayerTurns>,
 ) {
-  let texture = match player_name {
-    "Cookie Crab" => textures.crab.clone(),
-    "Sid Starfish" => textures.starfish.clone(),
-    "Foo Frog" => textures.frog.clone(),
-    "Jabby Jellyfish" => textures.jellyfish.clone(),
-    _ => textures.crab.clone(),
-  };
-  let tag = match player_name {
-    "Cookie Crab" => game::PlayerTag::Player1,
-    "Sid Starfish" => game::PlayerTag::Player2,
-    "Foo Frog" => game::PlayerTag::Player3,
-    "Jabby Jellyfish" => game::PlayerTag::Player4,
-    _ => game::PlayerTag::Player1,
-  };
-  commands
-    .spawn(SpriteBundle {
-      texture: texture,
-      transform: Transform::from_translation(Vec3::new(0., 0., 1.)),
-      ..Default::default()
-    })
-    .insert(PlayerHead {
-      direction: direction,
-    })
-    .insert(start_position)
-    .insert(Player {
-      name: player_name.to_string(),
-      player_tag: tag,
+  for player in heads.iter() {
+    let slot = roster.get(player.player_tag);
+    let Some(gamepad) = slot.gamepad.filter(|_| slot.bot.is_none()) else {
+      continue;
+    };
+    let just_pressed = |button| gamepad_input.just_pressed(GamepadButton::new(gamepad, button));
+    let dpad = [
+      GamepadButtonType::DPadLeft,
+      GamepadButtonType::DPadRight,
+      GamepadButtonType::DPadUp,
+      GamepadButtonType::DPadDown,
+    ];
+    let turn = steering(slot.scheme, just_pressed, dpad).or_else(|| {
+      if just_pressed(GamepadButtonType::LeftTrigger) {
+        Some(sim::Turn::Left)
+      } else if just_pressed(GamepadButtonType::RightTrigger) {
+        Some(sim::Turn::Right)
+      } else {
+        None
+      }
     });
+    if let Some(turn) = turn {
+      turns.push(player.player_tag, turn, *buffer);
+    }
+  }
 }
 
-fn get_all_positions(
-  segments: &PlayerSegments,
-  positions: &Query<&mut game::Position>,
-  heads: &Query<(Entity, &PlayerHead, &mut Player)>,
-) -> Vec<(game::Position, game::PlayerTag)> {
-  let mut all_positions = Vec::new();
-
-  // Create a mapping from player name to player tag.
-  let player_name_to_tag: HashMap<String, game::PlayerTag> = heads
+/// Creates the bots picked in the lobby for a new match
+fn create_bots(
+  roster: Res<roster::Roster>,
+  registry: Res<BotRegistry>,
+  mut bots: ResMut<SlotBots>,
+) {
+  bots.0 = roster
+    .slots
     .iter()
-    .map(|(_, _, player)| (player.name.clone(), player.player_tag))
+    .map(|slot| {
+      let name = slot.bot.as_deref()?;
+      registry.create(name, rand::random())
+    })
     .collect();
+}
 
-  for (player_name, segment_entities) in segments.0.iter() {
-    let player_tag = player_name_to_tag
-      .get(player_name)
-      .unwrap_or(&game::PlayerTag::Player1); // Default value or handle error
-
-    for entity in segment_entities {
-      if let Ok(pos) = positions.get(*entity) {
-        all_positions.push((*pos, *player_tag));
+/// Lets every bot decide on its move for the coming tick. Its turn is queued like a
+/// key press, just right before the tick consumes it. A disqualified bot's snake is
+/// taken out of the round on the spot.
+fn bot_input(
+  mut simulation: ResMut<Simulation>,
+  roster: Res<roster::Roster>,
+  buffer: Res<InputBuffer>,
+  mut bots: ResMut<SlotBots>,
+  mut turns: ResMut<PlayerTurns>,
+  mut eliminated: EventWriter<events::PlayerEliminated>,
+) {
+  for (slot, bot) in bots.0.iter_mut().enumerate() {
+    let Some(bot) = bot.as_mut().filter(|_| simulation.is_alive(slot)) else {
+      continue;
+    };
+    let tag = game::PlayerTag(slot);
+    match bot.decide(&bot::ArenaView::new(&simulation, slot)) {
+      Ok(Some(turn)) => turns.push(tag, turn, *buffer),
+      Ok(None) => {}
+      Err(bot::Disqualified(reason)) => {
+        warn!("{} is disqualified: {}", roster.get(tag).name, reason);
+        if let Some(elimination) = simulation.disqualify(slot) {
+          eliminated.send(events::PlayerEliminated {
+            tag,
+            cause: elimination.cause,
+            killer: None,
+            survivors: simulation.alive().collect(),
+          });
+        }
       }
     }
   }
-
-  all_positions
 }
 
-fn move_players(
-  mut segments: ResMut<PlayerSegments>,
-  mut heads: Query<(Entity, &PlayerHead, &mut Player)>,
-  mut positions: Query<&mut game::Position>,
-  mut in_game_state: ResMut<state::InGameState>,
-  mut scores: ResMut<state::PlayerScores>,
+/// Mirrors the set of living snakes onto head entities and the roster. Heads are
+/// spawned for snakes the simulation has added and removed for snakes it has dropped.
+/// Trails are drawn separately by the `trail` module.
+fn sync_heads(
   mut commands: Commands,
+  simulation: Res<Simulation>,
+  mut roster: ResMut<roster::Roster>,
+  heads: Query<(Entity, &Player), With<PlayerHead>>,
+  mut spawned: EventWriter<events::PlayerSpawned>,
 ) {
-  let segment_positions = get_all_positions(&segments, &positions, &heads);
-  let mut game_over_players = Vec::new();
-  for (head_entity, head, player) in heads.iter_mut() {
-    let mut head_pos = positions.get_mut(head_entity).unwrap();
-    match head.direction {
-      Direction::Left => {
-        head_pos.x += -1;
-      }
-      Direction::Right => {
-        head_pos.x += 1;
-      }
-      Direction::Up => {
-        head_pos.y += 1;
-      }
-      Direction::Down => {
-        head_pos.y -= 1;
-      }
-    }
-    if head_pos.x < 0
-      || head_pos.y < 0
-      || head_pos.x as u32 >= game::ARENA_WIDTH
-      || head_pos.y as u32 >= game::ARENA_HEIGHT
-    {
-      game_over_players.push(player.name.clone());
+  for (head_entity, player) in heads.iter() {
+    if simulation.is_alive(player.player_tag.index()) {
       continue;
     }
-    // Assuming all_segment_data is a Vec<(game::Position, game::PlayerTag)>
-    for (pos, player_tag) in &segment_positions {
-      if *pos == *head_pos {
-        game_over_players.push(player.name.clone());
-        if *player_tag == player.player_tag {
-          // The player ran into their own segment, so deduct points
-          match player_tag {
-            game::PlayerTag::Player1 => scores.player1 -= 1,
-            game::PlayerTag::Player2 => scores.player2 -= 1,
-            game::PlayerTag::Player3 => scores.player3 -= 1,
-            game::PlayerTag::Player4 => scores.player4 -= 1,
-          }
-        } else {
-          // The player ran into another player's segment, so add points
-          match player_tag {
-            game::PlayerTag::Player1 => scores.player1 += 1,
-            game::PlayerTag::Player2 => scores.player2 += 1,
-            game::PlayerTag::Player3 => scores.player3 += 1,
-            game::PlayerTag::Player4 => scores.player4 += 1,
-          }
-        }
-        continue; // or continue depending on your game logic
-      }
-    }
+    commands.entity(head_entity).despawn();
   }
-
-  for player_name in &game_over_players {
-    if let Some(player_segments) = segments.0.remove(player_name) {
-      for segment in player_segments {
-        commands.entity(segment).despawn();
-      }
-    }
-
-    if let Some(head_entity) = heads.iter().find_map(|(entity, _, player)| {
-      if player.name == *player_name {
-        Some(entity)
-      } else {
-        None
-      }
-    }) {
-      match player_name.as_str() {
-        "Cookie Crab" => in_game_state.player1 = false,
-        "Sid Starfish" => in_game_state.player2 = false,
-        "Foo Frog" => in_game_state.player3 = false,
-        "Jabby Jellyfish" => in_game_state.player4 = false,
-        _ => (),
-      }
-      commands.entity(head_entity).despawn();
+  for tag in roster.tags() {
+    let slot = roster.get_mut(tag);
+    slot.alive = simulation.is_alive(tag.index());
+    let Some(snake) = simulation.snake(tag.index()) else {
+      continue;
+    };
+    if heads.iter().all(|(_, player)| player.player_tag != tag) {
+      spawn_player(&mut commands, slot, tag, snake.head);
+      spawned.send(events::PlayerSpawned {
+        tag,
+        position: snake.head,
+        direction: snake.direction,
+      });
     }
   }
 }
 
-fn grow_player_tails(
-  mut commands: Commands,
-  head_positions: Query<(&game::Position, &Player), With<PlayerHead>>,
-  mut segments: ResMut<PlayerSegments>,
-  game_state: Res<state::InGameState>,
-) {
-  for (head_position, player) in head_positions.iter() {
-    let is_alive = match player.player_tag {
-      game::PlayerTag::Player1 => game_state.player1,
-      game::PlayerTag::Player2 => game_state.player2,
-      game::PlayerTag::Player3 => game_state.player3,
-      game::PlayerTag::Player4 => game_state.player4,
-    };
-    if !is_alive {
-      continue;
-    }
-    let player_segments = segments
-      .0
-      .entry(player.name.clone())
-      .or_insert_with(Vec::new);
-    player_segments.push(spawn_segment(
-      &mut commands,
-      head_position.clone(),
-      player.clone(),
-    ));
+fn despawn_players(mut commands: Commands, heads: Query<Entity, With<PlayerHead>>) {
+  for head_entity in heads.iter() {
+    commands.entity(head_entity).despawn();
   }
 }
 
-fn spawn_segment(commands: &mut Commands, position: game::Position, player: Player) -> Entity {
-  let color = match &player.name as &str {
-    "Cookie Crab" => COLOR_P1,
-    "Sid Starfish" => COLOR_P2,
-    "Foo Frog" => COLOR_P3,
-    "Jabby Jellyfish" => COLOR_P4,
-    _ => Color::rgb(0.45, 0.0, 0.99), // Default color
-  };
+fn spawn_player(
+  commands: &mut Commands,
+  slot: &roster::PlayerSlot,
+  tag: game::PlayerTag,
+  start_position: sim::Position,
+) {
   commands
     .spawn(SpriteBundle {
       sprite: Sprite {
-        color: color,
-        custom_size: Some(Vec2::new(3.0, 3.0)),
+        color: slot.tint,
         ..default()
       },
-      ..default()
+      texture: slot.sprite.clone(),
+      transform: Transform::from_translation(Vec3::new(0., 0., 1.)),
+      ..Default::default()
     })
-    .insert(position)
-    .insert(player)
-    .id()
+    .insert(PlayerHead)
+    .insert(game::Position(start_position))
+    .insert(game::PreviousPosition(start_position))
+    .insert(Player { player_tag: tag });
+}
+
+/// Steps the simulation once, mirrors the result onto the head entities and reports
+/// every turn and elimination
+fn move_players(
+  mut simulation: ResMut<Simulation>,
+  mut turns: ResMut<PlayerTurns>,
+  mut heads: Query<(&Player, &mut game::Position, &mut game::PreviousPosition), With<PlayerHead>>,
+  mut turned: EventWriter<events::PlayerTurned>,
+  mut eliminated: EventWriter<events::PlayerEliminated>,
+) {
+  let directions: Vec<Option<sim::Direction>> = (0..roster::MAX_PLAYERS)
+    .map(|slot| simulation.snake(slot).map(|snake| snake.direction))
+    .collect();
+  let next = turns.next_tick(&simulation);
+  let eliminations = simulation.step(&next);
+
+  for (player, mut position, mut previous) in heads.iter_mut() {
+    if let Some(snake) = simulation.snake(player.player_tag.index()) {
+      previous.0 = position.0;
+      position.0 = snake.head;
+    }
+  }
+
+  for (slot, before) in directions.into_iter().enumerate() {
+    let Some(snake) = simulation.snake(slot) else {
+      continue;
+    };
+    if before.is_some_and(|before| before != snake.direction) {
+      turned.send(events::PlayerTurned {
+        tag: game::PlayerTag(slot),
+        direction: snake.direction,
+      });
+    }
+  }
+  let survivors: Vec<usize> = simulation.alive().collect();
+  for elimination in eliminations {
+    let killer = match elimination.cause {
+      sim::Cause::Trail(owner) => Some(game::PlayerTag(owner)),
+      _ => None,
+    };
+    eliminated.send(events::PlayerEliminated {
+      tag: game::PlayerTag(elimination.slot),
+      cause: elimination.cause,
+      killer,
+      survivors: survivors.clone(),
+    });
+  }
 }
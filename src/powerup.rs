@@ -0,0 +1,73 @@
+use bevy::prelude::*;
+
+use crate::{game, player, sim, state};
+
+pub struct PowerUpPlugin;
+
+/// A power-up lying in the arena, the nth in `sim::Simulation::items`
+#[derive(Component)]
+struct ItemSprite(usize);
+
+/// This plugin shows the power-ups the simulation drops into the arena. Picking them
+/// up and their effects are handled by the simulation itself.
+impl Plugin for PowerUpPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .add_state::<state::AppState>()
+      .add_systems(Update, sync_items.run_if(in_state(state::AppState::Game)));
+    for match_end in state::MATCH_END {
+      app.add_systems(OnEnter(match_end), despawn_items);
+    }
+  }
+}
+
+/// Color of a power-up in the arena and of its timer in the HUD
+pub fn color(power_up: sim::PowerUp) -> Color {
+  match power_up {
+    sim::PowerUp::Speed => Color::rgb(1.0, 0.9, 0.2),
+    sim::PowerUp::Slow => Color::rgb(0.4, 0.5, 1.0),
+    sim::PowerUp::Eraser => Color::WHITE,
+    sim::PowerUp::Ghost => Color::rgb(0.7, 0.7, 0.7),
+    sim::PowerUp::Wrap => Color::rgb(0.9, 0.3, 0.9),
+  }
+}
+
+/// Keeps one sprite on every power-up in the arena
+fn sync_items(
+  mut commands: Commands,
+  simulation: Res<player::Simulation>,
+  mut sprites: Query<(Entity, &ItemSprite, &mut game::Position, &mut Sprite)>,
+) {
+  let items = simulation.items();
+  for (entity, item_sprite, mut position, mut sprite) in sprites.iter_mut() {
+    let Some(item) = items.get(item_sprite.0) else {
+      commands.entity(entity).despawn();
+      continue;
+    };
+    if position.0 != item.position {
+      position.0 = item.position;
+      sprite.color = color(item.power_up);
+    }
+  }
+  for (index, item) in items.iter().enumerate().skip(sprites.iter().len()) {
+    commands.spawn((
+      SpriteBundle {
+        sprite: Sprite {
+          color: color(item.power_up),
+          custom_size: Some(Vec2::new(7.0, 7.0)),
+          ..default()
+        },
+        transform: Transform::from_translation(Vec3::new(0., 0., 0.5)),
+        ..default()
+      },
+      game::Position(item.position),
+      ItemSprite(index),
+    ));
+  }
+}
+
+fn despawn_items(mut commands: Commands, sprites: Query<Entity, With<ItemSprite>>) {
+  for entity in sprites.iter() {
+    commands.entity(entity).despawn();
+  }
+}
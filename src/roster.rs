@@ -0,0 +1,245 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{bot, controls, game, init};
+
+/// Number of player slots in the roster
+pub const MAX_PLAYERS: usize = 8;
+
+/// The keys a player steers with. `up` and `down` are only used by the absolute scheme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TurnKeys {
+  pub left: KeyCode,
+  pub right: KeyCode,
+  pub up: KeyCode,
+  pub down: KeyCode,
+}
+
+impl TurnKeys {
+  pub fn all(self) -> [KeyCode; 4] {
+    [self.left, self.right, self.up, self.down]
+  }
+}
+
+/// How a player's keys or d-pad steer their snake
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ControlScheme {
+  /// Left and right turn relative to the current heading
+  #[default]
+  Relative,
+  /// Four keys each pick a heading on screen; reversing is ignored
+  Absolute,
+}
+
+impl ControlScheme {
+  pub fn label(self) -> &'static str {
+    match self {
+      ControlScheme::Relative => "Relative",
+      ControlScheme::Absolute => "Absolute",
+    }
+  }
+
+  pub fn next(self) -> Self {
+    match self {
+      ControlScheme::Relative => ControlScheme::Absolute,
+      ControlScheme::Absolute => ControlScheme::Relative,
+    }
+  }
+}
+
+/// Everything about one player slot: who they are, how they look, how they
+/// steer and how they are doing in the current match
+#[derive(Debug, Clone)]
+pub struct PlayerSlot {
+  pub name: String,
+  pub color: Color,
+  pub sprite: Handle<Image>,
+  /// Multiplied into the sprite, so characters can share an image
+  pub tint: Color,
+  pub keys: TurnKeys,
+  pub scheme: ControlScheme,
+  /// Gamepad that joined into this slot. It stays assigned after a disconnect so
+  /// the same player can pick the slot up again.
+  pub gamepad: Option<Gamepad>,
+  /// Name of the registered bot steering this slot instead of keys or a gamepad
+  pub bot: Option<String>,
+  pub score: i32,
+  pub round_wins: usize,
+  /// Takes part in the rounds of the current match
+  pub joined: bool,
+  /// Has a living snake in the arena, mirrored from the simulation
+  pub alive: bool,
+}
+
+impl PlayerSlot {
+  fn new(name: &str, color: Color, sprite: Handle<Image>, keys: [KeyCode; 4]) -> Self {
+    let [left, right, up, down] = keys;
+    PlayerSlot {
+      name: name.to_string(),
+      color,
+      sprite,
+      tint: Color::WHITE,
+      keys: TurnKeys {
+        left,
+        right,
+        up,
+        down,
+      },
+      scheme: ControlScheme::default(),
+      gamepad: None,
+      bot: None,
+      score: 0,
+      round_wins: 0,
+      joined: false,
+      alive: false,
+    }
+  }
+
+  /// Cycles the slot through both human control schemes and then every registered
+  /// bot. A bot takes part right away; handing the slot back to a human waits for
+  /// them to join again.
+  pub fn next_controller(&mut self, bots: &bot::BotRegistry) {
+    match &self.bot {
+      Some(current) => {
+        let mut names = bots.names().skip_while(|name| name != current);
+        self.bot = names.nth(1).map(str::to_string);
+      }
+      None => {
+        self.scheme = self.scheme.next();
+        // Back at the first scheme once both have been offered
+        if self.scheme == ControlScheme::default() {
+          self.bot = bots.names().next().map(str::to_string);
+        }
+      }
+    }
+    self.joined = self.bot.is_some();
+    self.gamepad = None;
+  }
+
+  /// Who steers this slot, as shown in the lobby
+  pub fn controller_label(&self) -> &str {
+    self.bot.as_deref().unwrap_or(self.scheme.label())
+  }
+
+  /// Colors the shared sprite with the slot color
+  fn tinted(self) -> Self {
+    PlayerSlot {
+      tint: self.color,
+      ..self
+    }
+  }
+}
+
+/// All player slots, indexed by `game::PlayerTag::index`. Systems read names,
+/// colors, sprites and bindings from here instead of matching on players.
+#[derive(Resource, Debug, Clone)]
+pub struct Roster {
+  pub slots: Vec<PlayerSlot>,
+}
+
+impl Roster {
+  pub fn new(textures: &init::TextureAssets) -> Self {
+    Roster {
+      slots: vec![
+        PlayerSlot::new(
+          "Cookie Crab",
+          Color::rgb(0.99, 0.45, 0.0),
+          textures.crab.clone(),
+          [KeyCode::Q, KeyCode::W, KeyCode::Key2, KeyCode::S],
+        ),
+        PlayerSlot::new(
+          "Sid Starfish",
+          Color::rgb(0.99, 0.25, 0.20),
+          textures.starfish.clone(),
+          [KeyCode::B, KeyCode::N, KeyCode::H, KeyCode::M],
+        ),
+        PlayerSlot::new(
+          "Foo Frog",
+          Color::rgb(0.5, 0.95, 0.3),
+          textures.frog.clone(),
+          [KeyCode::O, KeyCode::P, KeyCode::Key0, KeyCode::Semicolon],
+        ),
+        PlayerSlot::new(
+          "Jabby Jellyfish",
+          Color::rgb(0.3, 0.75, 0.99),
+          textures.jellyfish.clone(),
+          [KeyCode::Left, KeyCode::Right, KeyCode::Up, KeyCode::Down],
+        ),
+        PlayerSlot::new(
+          "Otto Octopus",
+          Color::rgb(0.95, 0.85, 0.2),
+          textures.jellyfish.clone(),
+          [KeyCode::Z, KeyCode::X, KeyCode::D, KeyCode::C],
+        )
+        .tinted(),
+        PlayerSlot::new(
+          "Sally Seahorse",
+          Color::rgb(0.9, 0.35, 0.9),
+          textures.starfish.clone(),
+          [KeyCode::T, KeyCode::Y, KeyCode::Key6, KeyCode::G],
+        )
+        .tinted(),
+        PlayerSlot::new(
+          "Walter Walrus",
+          Color::rgb(0.9, 0.9, 0.9),
+          textures.crab.clone(),
+          [KeyCode::K, KeyCode::L, KeyCode::I, KeyCode::Comma],
+        )
+        .tinted(),
+        PlayerSlot::new(
+          "Penny Penguin",
+          Color::rgb(0.2, 0.9, 0.75),
+          textures.frog.clone(),
+          [
+            KeyCode::Numpad4,
+            KeyCode::Numpad6,
+            KeyCode::Numpad8,
+            KeyCode::Numpad5,
+          ],
+        )
+        .tinted(),
+      ],
+    }
+  }
+
+  pub fn tags(&self) -> impl Iterator<Item = game::PlayerTag> {
+    (0..self.slots.len()).map(game::PlayerTag)
+  }
+
+  pub fn get(&self, tag: game::PlayerTag) -> &PlayerSlot {
+    &self.slots[tag.index()]
+  }
+
+  pub fn get_mut(&mut self, tag: game::PlayerTag) -> &mut PlayerSlot {
+    &mut self.slots[tag.index()]
+  }
+
+  pub fn joined(&self) -> impl Iterator<Item = (game::PlayerTag, &PlayerSlot)> {
+    self
+      .tags()
+      .zip(self.slots.iter())
+      .filter(|(_, slot)| slot.joined)
+  }
+
+  /// Adds (slot, points) pairs handed out by a `scoring::ScoringScheme`
+  pub fn award(&mut self, points: Vec<(usize, i32)>) {
+    for (slot, points) in points {
+      self.slots[slot].score += points;
+    }
+  }
+
+  /// Clears scores and round wins for a new match, keeping who has joined
+  pub fn reset_match(&mut self) {
+    for slot in self.slots.iter_mut() {
+      slot.score = 0;
+      slot.round_wins = 0;
+      slot.alive = false;
+    }
+  }
+}
+
+pub fn setup_roster(mut commands: Commands, textures: Res<init::TextureAssets>) {
+  let mut roster = Roster::new(&textures);
+  controls::load_bindings(&mut roster);
+  commands.insert_resource(roster);
+}
@@ -0,0 +1,361 @@
+use bevy::prelude::*;
+
+use super::despawn_screen;
+use crate::{events, game, init, levels, player, roster, sim, state};
+
+pub struct RoundPlugin;
+
+const COUNTDOWN_SECONDS: f32 = 3.0;
+const ROUND_END_SECONDS: f32 = 3.0;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RoundPhase {
+  /// Waiting for at least two players to join
+  #[default]
+  Waiting,
+  /// "3-2-1" before everyone who joined is spawned
+  Countdown,
+  Playing,
+  /// Showing the winner before the next round starts
+  Ended,
+}
+
+#[derive(Resource)]
+pub struct Round {
+  pub phase: RoundPhase,
+  timer: Timer,
+  /// Slot that won the round shown in the `Ended` phase
+  winner: Option<usize>,
+  /// Simulation tick the round in progress started on
+  started: u64,
+  /// Every slot's score when the round in progress started
+  scores: Vec<i32>,
+}
+
+impl Default for Round {
+  fn default() -> Self {
+    Round {
+      phase: RoundPhase::Waiting,
+      timer: Timer::from_seconds(COUNTDOWN_SECONDS, TimerMode::Once),
+      winner: None,
+      started: 0,
+      scores: Vec::new(),
+    }
+  }
+}
+
+impl Round {
+  fn enter(&mut self, phase: RoundPhase, seconds: f32) {
+    self.phase = phase;
+    self.timer = Timer::from_seconds(seconds, TimerMode::Once);
+  }
+
+  /// Ticks the round in progress has lasted
+  pub fn ticks(&self, simulation: &player::Simulation) -> u64 {
+    simulation.tick() - self.started
+  }
+
+  /// Starts playing the round just counted down to
+  fn begin(&mut self, roster: &roster::Roster, simulation: &player::Simulation) {
+    self.winner = None;
+    self.started = simulation.tick();
+    self.scores = roster.slots.iter().map(|slot| slot.score).collect();
+    self.phase = RoundPhase::Playing;
+  }
+
+  /// Throws away the round in progress, or the one just finished, and counts down to
+  /// it again. The points it handed out are taken back, and a round that already
+  /// produced a winner is taken off their record.
+  pub fn restart(
+    &mut self,
+    in_game_state: &mut state::InGameState,
+    roster: &mut roster::Roster,
+    simulation: &mut player::Simulation,
+  ) {
+    if matches!(self.phase, RoundPhase::Playing | RoundPhase::Ended) {
+      in_game_state.current_round -= 1;
+      for (slot, score) in roster.slots.iter_mut().zip(&self.scores) {
+        slot.score = *score;
+      }
+      if let Some(slot) = self.winner.take() {
+        roster.slots[slot].round_wins -= 1;
+      }
+      in_game_state.winning_player = None;
+    }
+    if self.phase != RoundPhase::Waiting {
+      simulation.clear();
+      self.enter(RoundPhase::Countdown, COUNTDOWN_SECONDS);
+    }
+  }
+}
+
+/// Run condition for systems that should only advance while a round is being played
+pub fn playing(round: Res<Round>) -> bool {
+  round.phase == RoundPhase::Playing
+}
+
+// Tag component used to tag entities added for the round overlay
+#[derive(Component)]
+struct OnRound;
+
+#[derive(Component)]
+struct RoundBanner;
+
+/// This plugin runs the round flow: players join, a countdown spawns them all at
+/// once, and the round ends with a winner banner when one or zero snakes are left.
+/// The next round then starts on its own.
+impl Plugin for RoundPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .add_state::<state::AppState>()
+      .init_resource::<Round>()
+      .add_systems(
+        Update,
+        (advance_round, score_round, banner_update)
+          .chain()
+          .run_if(in_state(state::AppState::Game)),
+      )
+      .add_systems(
+        FixedUpdate,
+        score_eliminations
+          .after(player::SimulationStep)
+          .run_if(in_state(state::AppState::Game)),
+      );
+    for transition in state::MATCH_START {
+      app.add_systems(transition, (reset_round, banner_setup));
+    }
+    for match_end in state::MATCH_END {
+      app.add_systems(OnEnter(match_end), despawn_screen::<OnRound>);
+    }
+  }
+}
+
+/// Starts a fresh match. Players who joined in the lobby or the previous match stay in.
+fn reset_round(
+  settings: Res<state::MatchSettings>,
+  levels: Res<levels::Levels>,
+  mut round: ResMut<Round>,
+  mut in_game_state: ResMut<state::InGameState>,
+  mut roster: ResMut<roster::Roster>,
+  mut simulation: ResMut<player::Simulation>,
+) {
+  *round = Round::default();
+  *in_game_state = state::InGameState::default();
+  roster.reset_match();
+  *simulation = player::Simulation::default();
+  simulation.set_trail_mode(settings.trail_mode);
+  simulation.set_power_ups(settings.power_ups);
+  simulation.set_wrap_around(settings.wrap_around);
+  simulation.set_level(levels.get(settings.level).clone());
+}
+
+fn advance_round(
+  time: Res<Time>,
+  settings: Res<state::MatchSettings>,
+  mut roster: ResMut<roster::Roster>,
+  mut round: ResMut<Round>,
+  mut in_game_state: ResMut<state::InGameState>,
+  mut simulation: ResMut<player::Simulation>,
+  mut turns: ResMut<player::PlayerTurns>,
+  mut bots: ResMut<player::SlotBots>,
+  mut next_state: ResMut<NextState<state::AppState>>,
+  mut started: EventWriter<events::RoundStarted>,
+  mut ended: EventWriter<events::RoundEnded>,
+) {
+  match round.phase {
+    RoundPhase::Waiting => {
+      if roster.joined().count() >= 2 {
+        round.enter(RoundPhase::Countdown, COUNTDOWN_SECONDS);
+      }
+    }
+    RoundPhase::Countdown => {
+      if round.timer.tick(time.delta()).finished() {
+        simulation.clear();
+        turns.clear();
+        let joined: Vec<usize> = roster.joined().map(|(tag, _)| tag.index()).collect();
+        for slot in simulation.spawn_all(&joined) {
+          warn!("The level's spawn point for slot {} is blocked", slot + 1);
+        }
+        bots.new_round();
+        in_game_state.current_round += 1;
+        in_game_state.winning_player = None;
+        round.begin(&roster, &simulation);
+        started.send(events::RoundStarted {
+          round: in_game_state.current_round,
+        });
+      }
+    }
+    RoundPhase::Playing => {
+      let alive: Vec<usize> = simulation.alive().collect();
+      if alive.len() <= 1 {
+        round.winner = alive.first().copied();
+        in_game_state.winning_player = round.winner.map(|slot| {
+          let winner = &mut roster.slots[slot];
+          winner.round_wins += 1;
+          winner.name.clone()
+        });
+        round.enter(RoundPhase::Ended, ROUND_END_SECONDS);
+        ended.send(events::RoundEnded {
+          round: in_game_state.current_round,
+          winner: round.winner.map(game::PlayerTag),
+        });
+      }
+    }
+    RoundPhase::Ended => {
+      if round.timer.tick(time.delta()).finished() {
+        simulation.clear();
+        if settings.format.is_decided(&in_game_state, &roster) {
+          next_state.set(state::AppState::GameOver);
+        } else {
+          round.enter(RoundPhase::Countdown, COUNTDOWN_SECONDS);
+        }
+      }
+    }
+  }
+}
+
+/// Hands out the points for every elimination in the tick just stepped, a bot
+/// disqualified before the step included
+fn score_eliminations(
+  settings: Res<state::MatchSettings>,
+  round: Res<Round>,
+  simulation: Res<player::Simulation>,
+  mut roster: ResMut<roster::Roster>,
+  mut eliminated: EventReader<events::PlayerEliminated>,
+) {
+  let ticks = round.ticks(&simulation);
+  for event in eliminated.iter() {
+    let elimination = sim::Elimination {
+      slot: event.tag.index(),
+      cause: event.cause,
+    };
+    roster.award(
+      settings
+        .scoring
+        .eliminated(elimination, &event.survivors, ticks),
+    );
+  }
+}
+
+/// Hands out the points for surviving a round once it has ended
+fn score_round(
+  settings: Res<state::MatchSettings>,
+  round: Res<Round>,
+  simulation: Res<player::Simulation>,
+  mut roster: ResMut<roster::Roster>,
+  mut ended: EventReader<events::RoundEnded>,
+) {
+  for event in ended.iter() {
+    let survivors: Vec<usize> = event.winner.iter().map(|tag| tag.index()).collect();
+    roster.award(
+      settings
+        .scoring
+        .round_ended(&survivors, round.ticks(&simulation)),
+    );
+  }
+}
+
+fn banner_update(
+  round: Res<Round>,
+  in_game_state: Res<state::InGameState>,
+  mut banners: Query<&mut Text, With<RoundBanner>>,
+) {
+  let message = match round.phase {
+    RoundPhase::Waiting => "Press your keys to join".to_string(),
+    RoundPhase::Countdown => {
+      let remaining = round.timer.remaining_secs().ceil().max(1.0);
+      format!("{}", remaining as u32)
+    }
+    RoundPhase::Playing => String::new(),
+    RoundPhase::Ended => match &in_game_state.winning_player {
+      Some(name) => format!("{} wins round {}!", name, in_game_state.current_round),
+      None => format!("Round {} is a draw!", in_game_state.current_round),
+    },
+  };
+  for mut text in banners.iter_mut() {
+    if text.sections[0].value != message {
+      text.sections[0].value = message.clone();
+    }
+  }
+}
+
+fn banner_setup(mut commands: Commands, fonts: Res<init::FontAssets>) {
+  commands
+    .spawn((
+      NodeBundle {
+        style: Style {
+          width: Val::Percent(100.0),
+          height: Val::Percent(100.0),
+          align_items: AlignItems::Center,
+          justify_content: JustifyContent::Center,
+          position_type: PositionType::Absolute,
+          ..default()
+        },
+        ..default()
+      },
+      OnRound,
+    ))
+    .with_children(|parent| {
+      parent.spawn((
+        TextBundle::from_section(
+          "",
+          TextStyle {
+            font: fonts.overpass.clone(),
+            font_size: 30.0,
+            color: Color::WHITE,
+          },
+        )
+        .with_text_alignment(TextAlignment::Center),
+        RoundBanner,
+      ));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn roster() -> roster::Roster {
+    roster::Roster::new(&init::TextureAssets {
+      crab: Handle::default(),
+      starfish: Handle::default(),
+      frog: Handle::default(),
+      jellyfish: Handle::default(),
+    })
+  }
+
+  #[test]
+  fn restarting_takes_back_the_points_of_the_round() {
+    let mut roster = roster();
+    let mut simulation = player::Simulation::default();
+    let mut in_game_state = state::InGameState::default();
+    let mut round = Round::default();
+    roster.award(vec![(0, 2)]);
+    round.begin(&roster, &simulation);
+    in_game_state.current_round = 1;
+    roster.award(vec![(0, 1), (1, 3)]);
+    roster.slots[1].round_wins = 1;
+    round.winner = Some(1);
+    round.enter(RoundPhase::Ended, ROUND_END_SECONDS);
+
+    round.restart(&mut in_game_state, &mut roster, &mut simulation);
+    assert_eq!(round.phase, RoundPhase::Countdown);
+    assert_eq!(in_game_state.current_round, 0);
+    assert_eq!((roster.slots[0].score, roster.slots[1].score), (2, 0));
+    assert_eq!(roster.slots[1].round_wins, 0);
+  }
+
+  #[test]
+  fn restarting_the_countdown_keeps_the_points() {
+    let mut roster = roster();
+    let mut simulation = player::Simulation::default();
+    let mut in_game_state = state::InGameState::default();
+    let mut round = Round::default();
+    round.begin(&roster, &simulation);
+    roster.award(vec![(0, 1)]);
+    round.enter(RoundPhase::Countdown, COUNTDOWN_SECONDS);
+
+    round.restart(&mut in_game_state, &mut roster, &mut simulation);
+    assert_eq!(roster.slots[0].score, 1);
+  }
+}
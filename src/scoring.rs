@@ -0,0 +1,165 @@
+//! How players earn points. A scheme looks at every elimination and at the end of
+//! every round and hands out points; it never touches the simulation itself.
+
+use crate::sim;
+
+/// Ticks a snake has to stay alive for one point under `ScoringScheme::SurvivalTime`
+const SURVIVAL_TICKS: u64 = 300;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ScoringScheme {
+  /// The last snake left in a round gets a point
+  #[default]
+  LastStanding,
+  /// Every snake still alive gets a point whenever an opponent goes out
+  Outlived,
+  /// Running into someone's trail gives its owner a point; running into your own
+  /// costs you one
+  KillCredit,
+  /// A point for every `SURVIVAL_TICKS` ticks a snake stays alive
+  SurvivalTime,
+}
+
+impl ScoringScheme {
+  /// Schemes offered in the lobby, in the order the button cycles through them
+  pub const CHOICES: [ScoringScheme; 4] = [
+    ScoringScheme::LastStanding,
+    ScoringScheme::Outlived,
+    ScoringScheme::KillCredit,
+    ScoringScheme::SurvivalTime,
+  ];
+
+  pub fn label(self) -> String {
+    match self {
+      ScoringScheme::LastStanding => "Last one standing",
+      ScoringScheme::Outlived => "Point per opponent outlived",
+      ScoringScheme::KillCredit => "Kills to the trail owner",
+      ScoringScheme::SurvivalTime => "Survival time",
+    }
+    .to_string()
+  }
+
+  pub fn next(self) -> Self {
+    let index = Self::CHOICES
+      .iter()
+      .position(|scheme| *scheme == self)
+      .unwrap_or(0);
+    Self::CHOICES[(index + 1) % Self::CHOICES.len()]
+  }
+
+  /// Points handed out for `elimination`, as (slot, points) pairs. `alive` are the
+  /// slots still in the round afterwards and `ticks` how long the round has lasted.
+  pub fn eliminated(
+    self,
+    elimination: sim::Elimination,
+    alive: &[usize],
+    ticks: u64,
+  ) -> Vec<(usize, i32)> {
+    match self {
+      ScoringScheme::LastStanding => Vec::new(),
+      ScoringScheme::Outlived => alive.iter().map(|slot| (*slot, 1)).collect(),
+      ScoringScheme::KillCredit => match elimination.cause {
+        sim::Cause::OwnTrail => vec![(elimination.slot, -1)],
+        sim::Cause::Trail(owner) => vec![(owner, 1)],
+        sim::Cause::Wall
+        | sim::Cause::Obstacle
+        | sim::Cause::HeadOn(_)
+        | sim::Cause::Disqualified => Vec::new(),
+      },
+      ScoringScheme::SurvivalTime => vec![(elimination.slot, survival_points(ticks))],
+    }
+  }
+
+  /// Points handed out when a round of `ticks` ends with `survivors` still alive
+  pub fn round_ended(self, survivors: &[usize], ticks: u64) -> Vec<(usize, i32)> {
+    match self {
+      ScoringScheme::LastStanding => survivors.iter().map(|slot| (*slot, 1)).collect(),
+      ScoringScheme::Outlived | ScoringScheme::KillCredit => Vec::new(),
+      ScoringScheme::SurvivalTime => survivors
+        .iter()
+        .map(|slot| (*slot, survival_points(ticks)))
+        .collect(),
+    }
+  }
+}
+
+fn survival_points(ticks: u64) -> i32 {
+  (ticks / SURVIVAL_TICKS) as i32
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn out(slot: usize, cause: sim::Cause) -> sim::Elimination {
+    sim::Elimination { slot, cause }
+  }
+
+  #[test]
+  fn last_standing_only_scores_the_survivor() {
+    let scheme = ScoringScheme::LastStanding;
+    assert!(scheme
+      .eliminated(out(0, sim::Cause::Trail(1)), &[1, 2], 50)
+      .is_empty());
+    assert_eq!(scheme.round_ended(&[2], 900), [(2, 1)]);
+    assert!(scheme.round_ended(&[], 900).is_empty());
+  }
+
+  #[test]
+  fn outlived_scores_everyone_still_alive() {
+    let scheme = ScoringScheme::Outlived;
+    assert_eq!(
+      scheme.eliminated(out(0, sim::Cause::Wall), &[1, 3], 50),
+      [(1, 1), (3, 1)]
+    );
+    assert!(scheme.round_ended(&[1], 900).is_empty());
+  }
+
+  #[test]
+  fn kill_credit_goes_to_the_trail_owner() {
+    let scheme = ScoringScheme::KillCredit;
+    assert_eq!(
+      scheme.eliminated(out(0, sim::Cause::Trail(2)), &[1, 2], 50),
+      [(2, 1)]
+    );
+    assert_eq!(
+      scheme.eliminated(out(0, sim::Cause::OwnTrail), &[1, 2], 50),
+      [(0, -1)]
+    );
+    for cause in [
+      sim::Cause::Wall,
+      sim::Cause::Obstacle,
+      sim::Cause::HeadOn(1),
+      sim::Cause::Disqualified,
+    ] {
+      assert!(scheme.eliminated(out(0, cause), &[1, 2], 50).is_empty());
+    }
+    assert!(scheme.round_ended(&[1], 900).is_empty());
+  }
+
+  #[test]
+  fn survival_time_scores_whole_periods() {
+    let scheme = ScoringScheme::SurvivalTime;
+    assert_eq!(
+      scheme.eliminated(out(0, sim::Cause::Wall), &[1], SURVIVAL_TICKS - 1),
+      [(0, 0)]
+    );
+    assert_eq!(
+      scheme.eliminated(out(0, sim::Cause::Wall), &[1], 2 * SURVIVAL_TICKS + 5),
+      [(0, 2)]
+    );
+    assert_eq!(
+      scheme.round_ended(&[1, 2], 3 * SURVIVAL_TICKS),
+      [(1, 3), (2, 3)]
+    );
+  }
+
+  #[test]
+  fn next_cycles_through_every_choice() {
+    let mut scheme = ScoringScheme::default();
+    for expected in ScoringScheme::CHOICES.iter().cycle().skip(1).take(4) {
+      scheme = scheme.next();
+      assert_eq!(scheme, *expected);
+    }
+  }
+}
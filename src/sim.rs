@@ -0,0 +1,1451 @@
+//! Headless rules of the game: arena bounds, snake movement, trails and collisions.
+//! Nothing in here touches windows, sprites or schedules, so the Bevy plugins only
+//! feed inputs in and mirror the resulting state into entities.
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+pub const ARENA_WIDTH: u32 = 640;
+pub const ARENA_HEIGHT: u32 = 360;
+
+/// How far inside the arena edges planned spawns are
+const SPAWN_MARGIN: f32 = 50.0;
+/// Free cells a new snake needs in front of it
+const SPAWN_RUNWAY: i32 = 40;
+/// Closest two snakes may start to each other, in cells walked along the grid
+const SPAWN_DISTANCE: i32 = 60;
+/// Random spots tried when a planned spawn is blocked
+const SPAWN_ATTEMPTS: usize = 200;
+
+/// Trail length a snake starts with when trails grow on food
+const FOOD_START_LENGTH: usize = 30;
+/// Extra trail length for every pickup eaten
+const FOOD_GROWTH: usize = 30;
+/// Pickups lying in the arena at any time
+const FOOD_COUNT: usize = 3;
+
+/// Ticks between attempts to drop a new power-up into the arena
+const ITEM_INTERVAL: u64 = 240;
+/// Power-ups lying in the arena at most
+const MAX_ITEMS: usize = 3;
+/// Random cells tried for a pickup before looking at every cell
+const DROP_ATTEMPTS: usize = 100;
+/// How long a timed power-up lasts, in ticks
+const EFFECT_TICKS: u32 = 300;
+/// Ticks `Occupancy` remembers which cells changed in
+const CHANGE_HISTORY: u64 = 60;
+
+/// How long trails get
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Default)]
+pub enum TrailMode {
+  /// Light-cycle rules: trails never shrink
+  #[default]
+  Endless,
+  /// The oldest cell is dropped once a trail is this long
+  Fixed(usize),
+  /// Classic snake: trails start short and only get longer by eating pickups
+  Food,
+}
+
+impl TrailMode {
+  pub const CHOICES: [TrailMode; 3] = [TrailMode::Endless, TrailMode::Fixed(200), TrailMode::Food];
+
+  pub fn label(self) -> String {
+    match self {
+      TrailMode::Endless => "Endless trails".to_string(),
+      TrailMode::Fixed(length) => format!("Trails of {} cells", length),
+      TrailMode::Food => "Trails grow on food".to_string(),
+    }
+  }
+
+  pub fn next(self) -> Self {
+    let index = Self::CHOICES
+      .iter()
+      .position(|mode| *mode == self)
+      .unwrap_or(0);
+    Self::CHOICES[(index + 1) % Self::CHOICES.len()]
+  }
+
+  /// Trail length a fresh snake may reach, `None` for no limit
+  fn start_length(self) -> Option<usize> {
+    match self {
+      TrailMode::Endless => None,
+      TrailMode::Fixed(length) => Some(length),
+      TrailMode::Food => Some(FOOD_START_LENGTH),
+    }
+  }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Position {
+  pub x: i32,
+  pub y: i32,
+}
+
+impl Position {
+  pub fn step(self, direction: Direction) -> Self {
+    let (dx, dy) = match direction {
+      Direction::Left => (-1, 0),
+      Direction::Right => (1, 0),
+      Direction::Up => (0, 1),
+      Direction::Down => (0, -1),
+    };
+    Position {
+      x: self.x + dx,
+      y: self.y + dy,
+    }
+  }
+
+  pub fn in_arena(self) -> bool {
+    self.x >= 0 && self.y >= 0 && (self.x as u32) < ARENA_WIDTH && (self.y as u32) < ARENA_HEIGHT
+  }
+
+  /// The same cell brought back into the arena from the opposite edge
+  pub fn wrapped(self) -> Self {
+    Position {
+      x: self.x.rem_euclid(ARENA_WIDTH as i32),
+      y: self.y.rem_euclid(ARENA_HEIGHT as i32),
+    }
+  }
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Serialize, Deserialize)]
+pub enum Direction {
+  Left,
+  Up,
+  Right,
+  Down,
+}
+
+impl Direction {
+  pub fn turn_left(self) -> Self {
+    match self {
+      Self::Left => Self::Down,
+      Self::Right => Self::Up,
+      Self::Up => Self::Left,
+      Self::Down => Self::Right,
+    }
+  }
+  pub fn turn_right(self) -> Self {
+    match self {
+      Self::Left => Self::Up,
+      Self::Right => Self::Down,
+      Self::Up => Self::Right,
+      Self::Down => Self::Left,
+    }
+  }
+  pub fn opposite(self) -> Self {
+    self.turn_left().turn_left()
+  }
+  /// Facing the opposite way would drive straight into your own trail, so that
+  /// input is ignored
+  pub fn apply(self, turn: Turn) -> Self {
+    match turn {
+      Turn::Left => self.turn_left(),
+      Turn::Right => self.turn_right(),
+      Turn::Face(direction) if direction != self.opposite() => direction,
+      Turn::Face(_) => self,
+    }
+  }
+}
+
+/// A single steering input for one tick.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Turn {
+  /// Relative to the current heading
+  Left,
+  Right,
+  /// Absolute heading
+  Face(Direction),
+}
+
+/// A solid block of cells, `x` and `y` being its bottom left corner
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Obstacle {
+  pub x: i32,
+  pub y: i32,
+  pub width: i32,
+  pub height: i32,
+}
+
+impl Obstacle {
+  pub fn cells(self) -> impl Iterator<Item = Position> {
+    (self.y..self.y + self.height)
+      .flat_map(move |y| (self.x..self.x + self.width).map(move |x| Position { x, y }))
+  }
+}
+
+/// Where and facing which way the snake of a slot starts
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct SpawnPoint {
+  pub position: Position,
+  pub direction: Direction,
+}
+
+/// Static layout of the arena, loaded from a level file
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Level {
+  pub name: String,
+  #[serde(default)]
+  pub obstacles: Vec<Obstacle>,
+  /// Start of every slot, in slot order. Slots without one start at random.
+  #[serde(default)]
+  pub spawns: Vec<SpawnPoint>,
+}
+
+/// Which slot left a trail on every cell of the arena, and which cells are blocked by
+/// obstacles, so collisions are a single lookup instead of a scan over all trails.
+/// Kept in step with the snakes' trails. It also remembers which cells changed in the
+/// last `CHANGE_HISTORY` ticks, so whoever draws or mirrors the arena can catch up
+/// without looking at every cell.
+#[derive(Clone, Debug)]
+pub struct Occupancy {
+  cells: Vec<Option<usize>>,
+  obstacles: Vec<bool>,
+  /// Cells set recently, with the tick they were set in
+  changes: VecDeque<(u64, Position)>,
+  /// Changes dropped from the front of `changes` so far, so marks stay comparable
+  forgotten: u64,
+  tick: u64,
+}
+
+impl Default for Occupancy {
+  fn default() -> Self {
+    Occupancy {
+      cells: vec![None; (ARENA_WIDTH * ARENA_HEIGHT) as usize],
+      obstacles: vec![false; (ARENA_WIDTH * ARENA_HEIGHT) as usize],
+      changes: VecDeque::new(),
+      forgotten: 0,
+      tick: 0,
+    }
+  }
+}
+
+impl Occupancy {
+  fn index(position: Position) -> Option<usize> {
+    position
+      .in_arena()
+      .then(|| (position.y as u32 * ARENA_WIDTH + position.x as u32) as usize)
+  }
+
+  /// Slot whose trail covers `position`, if any
+  pub fn get(&self, position: Position) -> Option<usize> {
+    Self::index(position).and_then(|index| self.cells[index])
+  }
+
+  fn set(&mut self, position: Position, owner: Option<usize>) {
+    if let Some(index) = Self::index(position) {
+      self.cells[index] = owner;
+      self.changes.push_back((self.tick, position));
+    }
+  }
+
+  pub fn is_obstacle(&self, position: Position) -> bool {
+    Self::index(position).is_some_and(|index| self.obstacles[index])
+  }
+
+  /// Nothing covers `position`, neither a trail nor an obstacle
+  pub fn is_free(&self, position: Position) -> bool {
+    self.get(position).is_none() && !self.is_obstacle(position)
+  }
+
+  /// Removes all trails, leaving the obstacles
+  fn clear(&mut self) {
+    self.cells.iter_mut().for_each(|cell| *cell = None);
+    self.forget_changes();
+  }
+
+  fn set_obstacles(&mut self, level: &Level) {
+    self.forget_changes();
+    self.obstacles.iter_mut().for_each(|cell| *cell = false);
+    for position in level.obstacles.iter().flat_map(|obstacle| obstacle.cells()) {
+      if let Some(index) = Self::index(position) {
+        self.obstacles[index] = true;
+      }
+    }
+  }
+
+  /// The current point in the history of changes, to pass to `changed_since` later
+  pub fn mark(&self) -> u64 {
+    self.forgotten + self.changes.len() as u64
+  }
+
+  /// Cells covered or uncovered since `mark`, some maybe more than once. `None` if
+  /// that is too long ago to tell or the whole arena changed since; then every cell
+  /// has to be looked at again.
+  pub fn changed_since(&self, mark: u64) -> Option<impl Iterator<Item = Position> + '_> {
+    let skip = mark
+      .checked_sub(self.forgotten)
+      .filter(|skip| *skip <= self.changes.len() as u64)?;
+    Some(
+      self
+        .changes
+        .range(skip as usize..)
+        .map(|(_, position)| *position),
+    )
+  }
+
+  /// Moves on to `tick`, forgetting the changes older than `CHANGE_HISTORY` ticks
+  fn start_tick(&mut self, tick: u64) {
+    self.tick = tick;
+    while let Some((changed, _)) = self.changes.front() {
+      if changed + CHANGE_HISTORY > tick {
+        break;
+      }
+      self.changes.pop_front();
+      self.forgotten += 1;
+    }
+  }
+
+  /// Forgets every change, so every earlier mark is too old
+  fn forget_changes(&mut self) {
+    self.forgotten += self.changes.len() as u64 + 1;
+    self.changes.clear();
+  }
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Serialize)]
+pub enum PowerUp {
+  /// Moves three cells every two ticks
+  Speed,
+  /// Every other snake moves one cell every two ticks
+  Slow,
+  /// Clears every trail from the arena at once
+  Eraser,
+  /// Passes through trails without leaving one
+  Ghost,
+  /// Leaving the arena comes back in on the opposite edge
+  Wrap,
+}
+
+impl PowerUp {
+  pub const ALL: [PowerUp; 5] = [
+    PowerUp::Speed,
+    PowerUp::Slow,
+    PowerUp::Eraser,
+    PowerUp::Ghost,
+    PowerUp::Wrap,
+  ];
+
+  pub fn label(self) -> &'static str {
+    match self {
+      PowerUp::Speed => "Speed",
+      PowerUp::Slow => "Slow",
+      PowerUp::Eraser => "Eraser",
+      PowerUp::Ghost => "Ghost",
+      PowerUp::Wrap => "Wrap",
+    }
+  }
+}
+
+/// A power-up lying in the arena, waiting for a head to enter its cell
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct Item {
+  pub position: Position,
+  pub power_up: PowerUp,
+}
+
+/// A timed power-up working on a snake
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Serialize)]
+pub struct Effect {
+  pub power_up: PowerUp,
+  pub ticks_left: u32,
+}
+
+#[derive(Clone, Debug)]
+pub struct Snake {
+  pub head: Position,
+  pub direction: Direction,
+  /// Cells left behind by the head, oldest first
+  pub trail: VecDeque<Position>,
+  /// How many cells have dropped off the old end of the trail so far
+  pub trimmed: usize,
+  /// Longest the trail may get before old cells drop off, `None` for no limit
+  pub max_length: Option<usize>,
+  pub effects: Vec<Effect>,
+  /// A turn given in a tick the snake sat out, taken on its next move
+  held: Option<Turn>,
+}
+
+impl Snake {
+  pub fn has(&self, power_up: PowerUp) -> bool {
+    self
+      .effects
+      .iter()
+      .any(|effect| effect.power_up == power_up)
+  }
+
+  /// Starts `power_up`, or restarts it if it is already running
+  fn add_effect(&mut self, power_up: PowerUp) {
+    self.effects.retain(|effect| effect.power_up != power_up);
+    self.effects.push(Effect {
+      power_up,
+      ticks_left: EFFECT_TICKS,
+    });
+  }
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Cause {
+  Wall,
+  OwnTrail,
+  /// Ran into the trail of the player in the given slot
+  Trail(usize),
+  /// Ran into one of the level's obstacles
+  Obstacle,
+  /// Met the head of the player in the given slot, on the same cell or by swapping
+  /// cells with them. Both go out.
+  HeadOn(usize),
+  /// Taken out by the game, e.g. for a bot that broke the rules
+  Disqualified,
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct Elimination {
+  pub slot: usize,
+  pub cause: Cause,
+}
+
+/// The whole game state. Player slots are plain indices; the Bevy side maps them to
+/// `game::PlayerTag`s. Every random decision goes through the seeded rng so the same
+/// seed and inputs always replay to the same result.
+pub struct Simulation {
+  snakes: Vec<Option<Snake>>,
+  occupancy: Occupancy,
+  level: Level,
+  trail_mode: TrailMode,
+  food: Vec<Position>,
+  power_ups: bool,
+  /// Heads leaving the arena come back in on the opposite edge
+  wrap_around: bool,
+  items: Vec<Item>,
+  tick: u64,
+  rng: StdRng,
+}
+
+impl Simulation {
+  pub fn new(slots: usize, seed: u64) -> Self {
+    Simulation {
+      snakes: vec![None; slots],
+      occupancy: Occupancy::default(),
+      level: Level::default(),
+      trail_mode: TrailMode::default(),
+      food: Vec::new(),
+      power_ups: false,
+      wrap_around: false,
+      items: Vec::new(),
+      tick: 0,
+      rng: StdRng::seed_from_u64(seed),
+    }
+  }
+
+  pub fn snake(&self, slot: usize) -> Option<&Snake> {
+    self.snakes[slot].as_ref()
+  }
+
+  pub fn is_alive(&self, slot: usize) -> bool {
+    self.snakes[slot].is_some()
+  }
+
+  /// Slots that currently have a living snake, in slot order
+  pub fn alive(&self) -> impl Iterator<Item = usize> + '_ {
+    (0..self.snakes.len()).filter(|slot| self.is_alive(*slot))
+  }
+
+  /// Applies to snakes spawned from now on
+  pub fn set_trail_mode(&mut self, trail_mode: TrailMode) {
+    self.trail_mode = trail_mode;
+  }
+
+  /// Pickups currently lying in the arena
+  pub fn food(&self) -> &[Position] {
+    &self.food
+  }
+
+  /// Whether power-ups show up in the arena
+  pub fn set_power_ups(&mut self, power_ups: bool) {
+    self.power_ups = power_ups;
+  }
+
+  /// Puts the level's obstacles into the arena and uses its spawn points from now on
+  pub fn set_level(&mut self, level: Level) {
+    self.occupancy.set_obstacles(&level);
+    self.level = level;
+  }
+
+  pub fn level(&self) -> &Level {
+    &self.level
+  }
+
+  pub fn set_wrap_around(&mut self, wrap_around: bool) {
+    self.wrap_around = wrap_around;
+  }
+
+  pub fn wrap_around(&self) -> bool {
+    self.wrap_around
+  }
+
+  /// Power-ups currently lying in the arena
+  pub fn items(&self) -> &[Item] {
+    &self.items
+  }
+
+  /// Which snake's trail or which obstacle covers each cell
+  pub fn occupancy(&self) -> &Occupancy {
+    &self.occupancy
+  }
+
+  /// The cell `snake` moves to going in `direction`. It is outside the arena if the
+  /// snake would hit the wall there.
+  pub fn destination(&self, snake: &Snake, direction: Direction) -> Position {
+    let next = snake.head.step(direction);
+    if self.wrap_around || snake.has(PowerUp::Wrap) {
+      next.wrapped()
+    } else {
+      next
+    }
+  }
+
+  /// Removes every snake, trail and pickup from the arena
+  pub fn clear(&mut self) {
+    self.snakes.iter_mut().for_each(|snake| *snake = None);
+    self.occupancy.clear();
+    self.food.clear();
+    self.items.clear();
+  }
+
+  /// Takes the snake in `slot` out of the round outside of the usual collisions
+  pub fn disqualify(&mut self, slot: usize) -> Option<Elimination> {
+    self.snakes[slot].as_ref()?;
+    self.remove(slot);
+    Some(Elimination {
+      slot,
+      cause: Cause::Disqualified,
+    })
+  }
+
+  /// Takes the snake in `slot` and its trail off the arena
+  fn remove(&mut self, slot: usize) {
+    if let Some(snake) = self.snakes[slot].take() {
+      for position in snake.trail {
+        self.occupancy.set(position, None);
+      }
+    }
+  }
+
+  /// The snake in `slot` skips its move in the coming tick, being slowed
+  pub fn sits_out(&self, slot: usize) -> bool {
+    self.snakes[slot]
+      .as_ref()
+      .is_some_and(|snake| snake.has(PowerUp::Slow) && !self.tick.is_multiple_of(2))
+  }
+
+  /// Ticks stepped since the simulation was created
+  pub fn tick(&self) -> u64 {
+    self.tick
+  }
+
+  /// Puts a snake into the arena for every slot in `slots` that doesn't have one.
+  /// Slots the level has a spawn point for start there; the others are spread evenly
+  /// around a ring inside the arena edges, turned at random, and face into the arena.
+  /// A planned spot is only used if it and the runway in front of it are free and it
+  /// is far enough from everyone else, otherwise a random spot that is gets picked.
+  /// Returns the slots whose spawn point in the level is blocked by an obstacle or
+  /// another snake; they get a planned spot instead.
+  pub fn spawn_all(&mut self, slots: &[usize]) -> Vec<usize> {
+    let slots: Vec<usize> = slots
+      .iter()
+      .copied()
+      .filter(|slot| self.snakes[*slot].is_none())
+      .collect();
+    let (fixed, mut planned): (Vec<usize>, Vec<usize>) = slots
+      .iter()
+      .partition(|slot| self.level.spawns.get(**slot).is_some());
+    let mut blocked = Vec::new();
+    for slot in fixed {
+      let spawn = self.level.spawns[slot];
+      if self.is_vacant(spawn.position) {
+        self.spawn_at(slot, spawn.position, spawn.direction);
+      } else {
+        blocked.push(slot);
+      }
+    }
+    planned.extend(&blocked);
+
+    let center = (ARENA_WIDTH as f32 / 2.0, ARENA_HEIGHT as f32 / 2.0);
+    let radius = (center.0 - SPAWN_MARGIN, center.1 - SPAWN_MARGIN);
+    let turn = self.rng.gen::<f32>() * std::f32::consts::TAU;
+    for (index, slot) in planned.iter().copied().enumerate() {
+      let angle = turn + index as f32 * std::f32::consts::TAU / planned.len() as f32;
+      let ring = Position {
+        x: (center.0 + angle.cos() * radius.0) as i32,
+        y: (center.1 + angle.sin() * radius.1) as i32,
+      };
+      let mut head = ring;
+      let mut fair = false;
+      for _ in 0..SPAWN_ATTEMPTS {
+        fair = self.fair_spawn(head);
+        if fair {
+          break;
+        }
+        head = Position {
+          x: self.rng.gen_range(0..ARENA_WIDTH as i32),
+          y: self.rng.gen_range(0..ARENA_HEIGHT as i32),
+        };
+      }
+      if !fair {
+        head = self.scan_for_spawn().unwrap_or(head);
+      }
+      self.spawn_at(slot, head, Self::facing_center(head));
+    }
+    blocked
+  }
+
+  /// No trail, obstacle or head covers `position`, and it is inside the arena
+  fn is_vacant(&self, position: Position) -> bool {
+    position.in_arena()
+      && self.occupancy.is_free(position)
+      && self
+        .snakes
+        .iter()
+        .flatten()
+        .all(|snake| snake.head != position)
+  }
+
+  /// A random pick of the fair spawn spots, or failing that of the vacant cells, found
+  /// by looking at every cell. For arenas so full that random tries keep missing.
+  /// `None` only if no cell is vacant at all.
+  fn scan_for_spawn(&mut self) -> Option<Position> {
+    let vacant: Vec<Position> = (0..ARENA_HEIGHT as i32)
+      .flat_map(|y| (0..ARENA_WIDTH as i32).map(move |x| Position { x, y }))
+      .filter(|position| self.is_vacant(*position))
+      .collect();
+    let fair: Vec<Position> = vacant
+      .iter()
+      .copied()
+      .filter(|position| self.fair_spawn(*position))
+      .collect();
+    let choices = if fair.is_empty() { vacant } else { fair };
+    (!choices.is_empty()).then(|| choices[self.rng.gen_range(0..choices.len())])
+  }
+
+  /// The direction along which `position` is furthest from the center, pointing there
+  fn facing_center(position: Position) -> Direction {
+    let dx = (ARENA_WIDTH as f32 / 2.0 - position.x as f32) / ARENA_WIDTH as f32;
+    let dy = (ARENA_HEIGHT as f32 / 2.0 - position.y as f32) / ARENA_HEIGHT as f32;
+    match (dx.abs() > dy.abs(), dx > 0.0, dy > 0.0) {
+      (true, true, _) => Direction::Right,
+      (true, false, _) => Direction::Left,
+      (false, _, true) => Direction::Up,
+      (false, _, false) => Direction::Down,
+    }
+  }
+
+  /// `head` and `SPAWN_RUNWAY` cells in front of it are free, and no other head is
+  /// within `SPAWN_DISTANCE`
+  fn fair_spawn(&self, head: Position) -> bool {
+    let direction = Self::facing_center(head);
+    let runway_clear = (0..SPAWN_RUNWAY)
+      .scan(head, |position, _| {
+        let current = *position;
+        *position = position.step(direction);
+        Some(current)
+      })
+      .all(|position| position.in_arena() && self.occupancy.is_free(position));
+    let spaced =
+      self.snakes.iter().flatten().all(|snake| {
+        (snake.head.x - head.x).abs() + (snake.head.y - head.y).abs() >= SPAWN_DISTANCE
+      });
+    runway_clear && spaced
+  }
+
+  pub fn spawn_at(&mut self, slot: usize, head: Position, direction: Direction) {
+    self.remove(slot);
+    self.snakes[slot] = Some(Snake {
+      head,
+      direction,
+      trail: VecDeque::new(),
+      trimmed: 0,
+      max_length: self.trail_mode.start_length(),
+      effects: Vec::new(),
+      held: None,
+    });
+  }
+
+  /// Advances the game by one tick. `turns` is indexed by slot; missing entries mean
+  /// "keep going straight". Snakes under Speed get a second move in some ticks and
+  /// slowed ones skip some, so a tick runs in two rounds of moves.
+  pub fn step(&mut self, turns: &[Option<Turn>]) -> Vec<Elimination> {
+    self.occupancy.start_tick(self.tick);
+    if self.trail_mode == TrailMode::Food {
+      self.place_food();
+    }
+    let drop =
+      self.power_ups && self.tick.is_multiple_of(ITEM_INTERVAL) && self.items.len() < MAX_ITEMS;
+    if let Some(position) = drop.then(|| self.free_cell()).flatten() {
+      // Wrap does nothing in an arena that already wraps around
+      let choices = if self.wrap_around {
+        &PowerUp::ALL[..PowerUp::ALL.len() - 1]
+      } else {
+        &PowerUp::ALL[..]
+      };
+      let power_up = choices[self.rng.gen_range(0..choices.len())];
+      self.items.push(Item { position, power_up });
+    }
+    let even = self.tick.is_multiple_of(2);
+    let boosted = |snake: &Snake| snake.has(PowerUp::Speed) && even;
+    // A snake sitting out the tick keeps its turn for when it moves again; one already
+    // kept goes first and the new turn waits in its place
+    let mut turns: Vec<Option<Turn>> = (0..self.snakes.len())
+      .map(|slot| turns.get(slot).copied().flatten())
+      .collect();
+    let (first, resting): (Vec<usize>, Vec<usize>) =
+      self.alive().partition(|slot| !self.sits_out(*slot));
+    for slot in resting {
+      let snake = self.snakes[slot].as_mut().unwrap();
+      snake.held = snake.held.or(turns[slot].take());
+    }
+    for &slot in &first {
+      let snake = self.snakes[slot].as_mut().unwrap();
+      if let Some(held) = snake.held.take() {
+        snake.held = turns[slot].replace(held);
+      }
+    }
+    let mut eliminations = self.advance(&first, &turns);
+    let second: Vec<usize> = self
+      .alive()
+      .filter(|slot| boosted(self.snakes[*slot].as_ref().unwrap()))
+      .collect();
+    eliminations.extend(self.advance(&second, &[]));
+
+    for snake in self.snakes.iter_mut().flatten() {
+      snake
+        .effects
+        .iter_mut()
+        .for_each(|effect| effect.ticks_left -= 1);
+      snake.effects.retain(|effect| effect.ticks_left > 0);
+    }
+    self.tick += 1;
+    eliminations
+  }
+
+  /// Moves the snakes in `movers` one cell. Every head's next cell is worked out first
+  /// and all collisions are judged against the arena as it was before the move, so
+  /// the outcome doesn't depend on slot order. Snakes that die in the same move all
+  /// go out together; if that leaves nobody, the round is a draw. Every survivor
+  /// then leaves a trail cell where its head now is, unless it is a ghost.
+  fn advance(&mut self, movers: &[usize], turns: &[Option<Turn>]) -> Vec<Elimination> {
+    let moves: Vec<(usize, Direction, Position)> = movers
+      .iter()
+      .map(|&slot| {
+        let snake = self.snakes[slot].as_ref().unwrap();
+        let direction = match turns.get(slot).copied().flatten() {
+          Some(turn) => snake.direction.apply(turn),
+          None => snake.direction,
+        };
+        (slot, direction, self.destination(snake, direction))
+      })
+      .collect();
+
+    let mut eliminations = Vec::new();
+    for &(slot, _, next) in &moves {
+      let snake = self.snakes[slot].as_ref().unwrap();
+      let meets = |&&(other, _, other_next): &&(usize, Direction, Position)| {
+        other != slot && other_next == next
+      };
+      // Swapping cells means the two heads pass through each other. The other head's
+      // cell is already part of its trail, so this is checked before the trails.
+      let swaps = |&&(other, _, other_next): &&(usize, Direction, Position)| {
+        other != slot
+          && other_next == snake.head
+          && self.snakes[other].as_ref().unwrap().head == next
+      };
+      let trail_owner = match snake.has(PowerUp::Ghost) {
+        true => None,
+        false => self.occupancy.get(next),
+      };
+      let cause = if !next.in_arena() {
+        Cause::Wall
+      } else if self.occupancy.is_obstacle(next) {
+        Cause::Obstacle
+      } else if let Some(&(other, _, _)) = moves.iter().find(swaps) {
+        Cause::HeadOn(other)
+      } else if let Some(owner) = trail_owner {
+        if owner == slot {
+          Cause::OwnTrail
+        } else {
+          Cause::Trail(owner)
+        }
+      } else if let Some(&(other, _, _)) = moves.iter().find(meets) {
+        Cause::HeadOn(other)
+      } else {
+        continue;
+      };
+      eliminations.push(Elimination { slot, cause });
+    }
+
+    for elimination in &eliminations {
+      self.remove(elimination.slot);
+    }
+    let mut collected = Vec::new();
+    for (slot, direction, next) in moves {
+      let Some(snake) = self.snakes[slot].as_mut() else {
+        continue;
+      };
+      snake.direction = direction;
+      snake.head = next;
+      if !snake.has(PowerUp::Ghost) {
+        snake.trail.push_back(next);
+        self.occupancy.set(next, Some(slot));
+      }
+      if let Some(eaten) = self.food.iter().position(|food| *food == next) {
+        self.food.swap_remove(eaten);
+        snake.max_length = snake.max_length.map(|length| length + FOOD_GROWTH);
+      }
+      if let Some(item) = self.items.iter().position(|item| item.position == next) {
+        collected.push((slot, self.items.swap_remove(item).power_up));
+      }
+      while snake.trail.len() > snake.max_length.unwrap_or(usize::MAX) {
+        let dropped = snake.trail.pop_front().unwrap();
+        snake.trimmed += 1;
+        self.occupancy.set(dropped, None);
+      }
+    }
+    for (slot, power_up) in collected {
+      self.collect(slot, power_up);
+    }
+    eliminations
+  }
+
+  fn collect(&mut self, slot: usize, power_up: PowerUp) {
+    match power_up {
+      PowerUp::Speed | PowerUp::Ghost | PowerUp::Wrap => {
+        if let Some(snake) = self.snakes[slot].as_mut() {
+          snake.add_effect(power_up);
+        }
+      }
+      PowerUp::Slow => {
+        for (other, snake) in self.snakes.iter_mut().enumerate() {
+          if let Some(snake) = snake.as_mut().filter(|_| other != slot) {
+            snake.add_effect(power_up);
+          }
+        }
+      }
+      PowerUp::Eraser => {
+        for snake in self.snakes.iter_mut().flatten() {
+          snake.trimmed += snake.trail.len();
+          snake.trail.clear();
+        }
+        self.occupancy.clear();
+      }
+    }
+  }
+
+  /// A random cell that no trail, obstacle, head, food or power-up covers, `None` if
+  /// the arena has none left. Looks at every cell once random tries keep missing.
+  fn free_cell(&mut self) -> Option<Position> {
+    let free = |simulation: &Self, position: Position| {
+      simulation.is_vacant(position)
+        && !simulation.food.contains(&position)
+        && simulation
+          .items
+          .iter()
+          .all(|item| item.position != position)
+    };
+    for _ in 0..DROP_ATTEMPTS {
+      let position = Position {
+        x: self.rng.gen_range(0..ARENA_WIDTH as i32),
+        y: self.rng.gen_range(0..ARENA_HEIGHT as i32),
+      };
+      if free(self, position) {
+        return Some(position);
+      }
+    }
+    let free: Vec<Position> = (0..ARENA_HEIGHT as i32)
+      .flat_map(|y| (0..ARENA_WIDTH as i32).map(move |x| Position { x, y }))
+      .filter(|position| free(self, *position))
+      .collect();
+    (!free.is_empty()).then(|| free[self.rng.gen_range(0..free.len())])
+  }
+
+  /// Tops up the pickups to `FOOD_COUNT`, as far as there are free cells
+  fn place_food(&mut self) {
+    while self.food.len() < FOOD_COUNT {
+      match self.free_cell() {
+        Some(position) => self.food.push(position),
+        None => break,
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn at(x: i32, y: i32) -> Position {
+    Position { x, y }
+  }
+
+  #[test]
+  fn stepping_moves_heads_and_lays_trail() {
+    let mut simulation = Simulation::new(1, 0);
+    simulation.spawn_at(0, at(10, 10), Direction::Right);
+    assert!(simulation.step(&[]).is_empty());
+    assert!(simulation.step(&[Some(Turn::Left)]).is_empty());
+    let snake = simulation.snake(0).unwrap();
+    assert_eq!(snake.direction, Direction::Up);
+    assert_eq!(snake.head, at(11, 10).step(Direction::Up));
+    assert_eq!(simulation.occupancy().get(at(11, 10)), Some(0));
+    assert_eq!(simulation.occupancy().get(snake.head), Some(0));
+    assert_eq!(simulation.tick(), 2);
+  }
+
+  #[test]
+  fn facing_backwards_is_ignored() {
+    let mut simulation = Simulation::new(1, 0);
+    simulation.spawn_at(0, at(10, 10), Direction::Right);
+    assert!(simulation
+      .step(&[Some(Turn::Face(Direction::Left))])
+      .is_empty());
+    assert_eq!(simulation.snake(0).unwrap().head, at(11, 10));
+  }
+
+  #[test]
+  fn leaving_the_arena_hits_the_wall() {
+    let mut simulation = Simulation::new(1, 0);
+    simulation.spawn_at(0, at(ARENA_WIDTH as i32 - 1, 5), Direction::Right);
+    let eliminations = simulation.step(&[]);
+    assert_eq!(
+      eliminations,
+      [Elimination {
+        slot: 0,
+        cause: Cause::Wall
+      }]
+    );
+    assert!(!simulation.is_alive(0));
+  }
+
+  #[test]
+  fn leaving_a_wrapping_arena_comes_back_in() {
+    let mut simulation = Simulation::new(1, 0);
+    simulation.set_wrap_around(true);
+    simulation.spawn_at(0, at(ARENA_WIDTH as i32 - 1, 5), Direction::Right);
+    assert!(simulation.step(&[]).is_empty());
+    assert_eq!(simulation.snake(0).unwrap().head, at(0, 5));
+  }
+
+  #[test]
+  fn running_into_a_trail_credits_its_owner() {
+    let mut simulation = Simulation::new(2, 0);
+    simulation.spawn_at(0, at(10, 10), Direction::Right);
+    simulation.spawn_at(1, at(15, 16), Direction::Down);
+    for _ in 0..5 {
+      assert!(simulation.step(&[]).is_empty());
+    }
+    // Slot 0 crossed (15, 10) before slot 1 got there
+    assert_eq!(
+      simulation.step(&[]),
+      [Elimination {
+        slot: 1,
+        cause: Cause::Trail(0)
+      }]
+    );
+    assert!(simulation.is_alive(0));
+    // The dead snake's trail is cleared, the survivor's stays
+    assert_eq!(simulation.occupancy().get(at(15, 11)), None);
+    assert_eq!(simulation.occupancy().get(at(15, 10)), Some(0));
+  }
+
+  #[test]
+  fn running_into_your_own_trail() {
+    let mut simulation = Simulation::new(1, 0);
+    simulation.spawn_at(0, at(10, 10), Direction::Right);
+    assert!(simulation.step(&[]).is_empty());
+    assert!(simulation.step(&[]).is_empty());
+    assert!(simulation.step(&[Some(Turn::Left)]).is_empty());
+    assert!(simulation.step(&[Some(Turn::Left)]).is_empty());
+    assert_eq!(
+      simulation.step(&[Some(Turn::Left)]),
+      [Elimination {
+        slot: 0,
+        cause: Cause::OwnTrail
+      }]
+    );
+  }
+
+  #[test]
+  fn heads_meeting_on_a_cell_both_go_out() {
+    let mut simulation = Simulation::new(2, 0);
+    simulation.spawn_at(0, at(10, 10), Direction::Right);
+    simulation.spawn_at(1, at(14, 10), Direction::Left);
+    assert!(simulation.step(&[]).is_empty());
+    assert_eq!(
+      simulation.step(&[]),
+      [
+        Elimination {
+          slot: 0,
+          cause: Cause::HeadOn(1)
+        },
+        Elimination {
+          slot: 1,
+          cause: Cause::HeadOn(0)
+        }
+      ]
+    );
+  }
+
+  #[test]
+  fn heads_swapping_cells_both_go_out() {
+    let mut simulation = Simulation::new(2, 0);
+    simulation.spawn_at(0, at(10, 10), Direction::Right);
+    simulation.spawn_at(1, at(13, 10), Direction::Left);
+    assert!(simulation.step(&[]).is_empty());
+    // The heads are now side by side on (11, 10) and (12, 10)
+    assert_eq!(
+      simulation.step(&[]),
+      [
+        Elimination {
+          slot: 0,
+          cause: Cause::HeadOn(1)
+        },
+        Elimination {
+          slot: 1,
+          cause: Cause::HeadOn(0)
+        }
+      ]
+    );
+  }
+
+  #[test]
+  fn collisions_do_not_depend_on_slot_order() {
+    // Slot 1 moves into the cell slot 0 leaves; slot 0's trail stays there
+    let mut simulation = Simulation::new(2, 0);
+    simulation.spawn_at(0, at(10, 10), Direction::Right);
+    simulation.spawn_at(1, at(11, 12), Direction::Down);
+    assert!(simulation.step(&[]).is_empty());
+    assert_eq!(
+      simulation.step(&[]),
+      [Elimination {
+        slot: 1,
+        cause: Cause::Trail(0)
+      }]
+    );
+    let mut simulation = Simulation::new(2, 0);
+    simulation.spawn_at(1, at(10, 10), Direction::Right);
+    simulation.spawn_at(0, at(11, 12), Direction::Down);
+    assert!(simulation.step(&[]).is_empty());
+    assert_eq!(
+      simulation.step(&[]),
+      [Elimination {
+        slot: 0,
+        cause: Cause::Trail(1)
+      }]
+    );
+  }
+
+  #[test]
+  fn occupancy_fills_and_frees_cells() {
+    let mut occupancy = Occupancy::default();
+    occupancy.set(at(3, 4), Some(2));
+    assert_eq!(occupancy.get(at(3, 4)), Some(2));
+    assert!(!occupancy.is_free(at(3, 4)));
+    assert!(occupancy.is_free(at(4, 3)));
+    occupancy.set(at(3, 4), None);
+    assert!(occupancy.is_free(at(3, 4)));
+    // Cells outside the arena are never covered
+    occupancy.set(at(-1, 0), Some(0));
+    assert_eq!(occupancy.get(at(-1, 0)), None);
+    assert_eq!(occupancy.get(at(ARENA_WIDTH as i32, 0)), None);
+  }
+
+  #[test]
+  fn clearing_occupancy_keeps_obstacles() {
+    let mut occupancy = Occupancy::default();
+    occupancy.set_obstacles(&Level {
+      obstacles: vec![Obstacle {
+        x: 10,
+        y: 20,
+        width: 3,
+        height: 2,
+      }],
+      ..Level::default()
+    });
+    occupancy.set(at(0, 0), Some(1));
+    occupancy.clear();
+    assert!(occupancy.is_free(at(0, 0)));
+    assert!(occupancy.is_obstacle(at(10, 20)));
+    assert!(occupancy.is_obstacle(at(12, 21)));
+    assert!(!occupancy.is_obstacle(at(13, 21)));
+    assert!(!occupancy.is_obstacle(at(10, 22)));
+    assert!(!occupancy.is_free(at(11, 20)));
+    // A new level replaces the old obstacles
+    occupancy.set_obstacles(&Level::default());
+    assert!(occupancy.is_free(at(10, 20)));
+  }
+
+  #[test]
+  fn occupancy_remembers_recent_changes() {
+    let mut simulation = Simulation::new(1, 0);
+    simulation.spawn_at(0, at(10, 10), Direction::Right);
+    let mark = simulation.occupancy().mark();
+    simulation.step(&[]);
+    simulation.step(&[]);
+    let changed: Vec<Position> = simulation
+      .occupancy()
+      .changed_since(mark)
+      .unwrap()
+      .collect();
+    assert_eq!(changed, [at(11, 10), at(12, 10)]);
+    let mark = simulation.occupancy().mark();
+    assert_eq!(
+      simulation.occupancy().changed_since(mark).unwrap().count(),
+      0
+    );
+
+    for _ in 0..=CHANGE_HISTORY {
+      simulation.step(&[]);
+    }
+    assert!(simulation.occupancy().changed_since(mark).is_none());
+    let mark = simulation.occupancy().mark();
+    simulation.clear();
+    assert!(simulation.occupancy().changed_since(mark).is_none());
+    let mark = simulation.occupancy().mark();
+    assert_eq!(
+      simulation.occupancy().changed_since(mark).unwrap().count(),
+      0
+    );
+    assert!(simulation.occupancy().changed_since(mark + 1).is_none());
+  }
+
+  #[test]
+  fn a_snake_going_out_frees_its_trail() {
+    let mut simulation = Simulation::new(1, 0);
+    simulation.spawn_at(0, at(ARENA_WIDTH as i32 - 4, 0), Direction::Right);
+    for _ in 0..3 {
+      assert!(simulation.step(&[]).is_empty());
+    }
+    assert_eq!(
+      simulation.occupancy().get(at(ARENA_WIDTH as i32 - 2, 0)),
+      Some(0)
+    );
+    assert_eq!(simulation.step(&[]).len(), 1);
+    assert!((1..4).all(|x| simulation
+      .occupancy()
+      .is_free(at(ARENA_WIDTH as i32 - x, 0))));
+  }
+
+  #[test]
+  fn fixed_trails_drop_their_oldest_cell() {
+    let mut simulation = Simulation::new(1, 0);
+    simulation.set_trail_mode(TrailMode::Fixed(3));
+    simulation.spawn_at(0, at(10, 10), Direction::Right);
+    for _ in 0..5 {
+      assert!(simulation.step(&[]).is_empty());
+    }
+    let snake = simulation.snake(0).unwrap();
+    assert_eq!(
+      Vec::from(snake.trail.clone()),
+      [at(13, 10), at(14, 10), at(15, 10)]
+    );
+    assert_eq!(snake.trimmed, 2);
+    assert!(simulation.occupancy().is_free(at(12, 10)));
+    assert_eq!(simulation.occupancy().get(at(13, 10)), Some(0));
+  }
+
+  #[test]
+  fn a_fixed_trail_can_be_followed_by_its_own_head() {
+    // Going round a 2 by 2 square only works while the trail is shorter than 4
+    let mut simulation = Simulation::new(1, 0);
+    simulation.set_trail_mode(TrailMode::Fixed(3));
+    simulation.spawn_at(0, at(10, 10), Direction::Right);
+    for _ in 0..8 {
+      assert!(simulation.step(&[Some(Turn::Left)]).is_empty());
+    }
+  }
+
+  #[test]
+  fn food_trails_grow_when_eating() {
+    let mut simulation = Simulation::new(1, 0);
+    simulation.set_trail_mode(TrailMode::Food);
+    simulation.spawn_at(0, at(10, 10), Direction::Right);
+    simulation.food = vec![at(12, 10)];
+    for _ in 0..FOOD_START_LENGTH + 10 {
+      assert!(simulation.step(&[]).is_empty());
+    }
+    let snake = simulation.snake(0).unwrap();
+    assert_eq!(snake.trail.len(), FOOD_START_LENGTH + 10);
+    assert_eq!(snake.max_length, Some(FOOD_START_LENGTH + FOOD_GROWTH));
+    // Eaten food is replaced to keep `FOOD_COUNT` in the arena
+    assert!(!simulation.food().contains(&at(12, 10)));
+    assert_eq!(simulation.food().len(), FOOD_COUNT);
+  }
+
+  #[test]
+  fn food_trails_stop_at_their_length() {
+    let mut simulation = Simulation::new(1, 0);
+    simulation.set_trail_mode(TrailMode::Food);
+    simulation.spawn_at(0, at(10, 100), Direction::Up);
+    for _ in 0..FOOD_START_LENGTH + 10 {
+      simulation.step(&[]);
+      // Keep the random food out of the way
+      simulation.food.clear();
+    }
+    let snake = simulation.snake(0).unwrap();
+    assert_eq!(snake.trail.len(), FOOD_START_LENGTH);
+    assert_eq!(snake.trimmed, 10);
+  }
+
+  fn drop_item(simulation: &mut Simulation, position: Position, power_up: PowerUp) {
+    simulation.items.push(Item { position, power_up });
+  }
+
+  #[test]
+  fn speed_moves_three_cells_every_two_ticks() {
+    let mut simulation = Simulation::new(1, 0);
+    simulation.spawn_at(0, at(10, 10), Direction::Right);
+    drop_item(&mut simulation, at(11, 10), PowerUp::Speed);
+    simulation.step(&[]);
+    // The boost starts in the tick the item is picked up
+    assert_eq!(simulation.snake(0).unwrap().head, at(12, 10));
+    simulation.step(&[]);
+    simulation.step(&[]);
+    assert_eq!(simulation.snake(0).unwrap().head, at(15, 10));
+    assert!(simulation.items().is_empty());
+  }
+
+  #[test]
+  fn slow_holds_back_everyone_else() {
+    let mut simulation = Simulation::new(2, 0);
+    simulation.spawn_at(0, at(10, 10), Direction::Right);
+    simulation.spawn_at(1, at(10, 50), Direction::Right);
+    drop_item(&mut simulation, at(11, 10), PowerUp::Slow);
+    for _ in 0..5 {
+      simulation.step(&[]);
+    }
+    assert_eq!(simulation.snake(0).unwrap().head, at(15, 10));
+    assert!(!simulation.snake(0).unwrap().has(PowerUp::Slow));
+    assert_eq!(simulation.snake(1).unwrap().head, at(13, 50));
+  }
+
+  #[test]
+  fn slowed_snakes_keep_turns_given_while_sitting_out() {
+    let mut simulation = Simulation::new(2, 0);
+    simulation.spawn_at(0, at(10, 10), Direction::Right);
+    simulation.spawn_at(1, at(10, 50), Direction::Right);
+    drop_item(&mut simulation, at(11, 10), PowerUp::Slow);
+    simulation.step(&[]);
+    assert!(simulation.sits_out(1));
+    // Slot 1 sits out tick 1, so its turn waits for tick 2
+    simulation.step(&[None, Some(Turn::Left)]);
+    assert_eq!(simulation.snake(1).unwrap().head, at(11, 50));
+    assert!(!simulation.sits_out(1));
+    simulation.step(&[]);
+    let snake = simulation.snake(1).unwrap();
+    assert_eq!((snake.head, snake.direction), (at(11, 51), Direction::Up));
+  }
+
+  #[test]
+  fn eraser_clears_every_trail() {
+    let mut simulation = Simulation::new(2, 0);
+    simulation.spawn_at(0, at(10, 10), Direction::Right);
+    simulation.spawn_at(1, at(10, 50), Direction::Right);
+    drop_item(&mut simulation, at(15, 10), PowerUp::Eraser);
+    for _ in 0..5 {
+      simulation.step(&[]);
+    }
+    assert!((11..=15).all(|x| simulation.occupancy().is_free(at(x, 10))));
+    assert!((11..=15).all(|x| simulation.occupancy().is_free(at(x, 50))));
+    assert!(simulation.snake(1).unwrap().trail.is_empty());
+    simulation.step(&[]);
+    assert_eq!(simulation.occupancy().get(at(16, 10)), Some(0));
+  }
+
+  #[test]
+  fn ghosts_pass_through_trails_without_leaving_one() {
+    let mut simulation = Simulation::new(2, 0);
+    simulation.spawn_at(0, at(10, 10), Direction::Right);
+    simulation.spawn_at(1, at(13, 8), Direction::Up);
+    drop_item(&mut simulation, at(11, 10), PowerUp::Ghost);
+    for _ in 0..5 {
+      assert!(simulation.step(&[]).is_empty());
+    }
+    // Slot 0 went through slot 1's trail on (13, 10)
+    assert_eq!(simulation.snake(0).unwrap().head, at(15, 10));
+    assert_eq!(simulation.occupancy().get(at(13, 10)), Some(1));
+    assert!(simulation.occupancy().is_free(at(14, 10)));
+    assert_eq!(simulation.occupancy().get(at(11, 10)), Some(0));
+  }
+
+  #[test]
+  fn wrap_leads_back_in_on_the_opposite_edge() {
+    let mut simulation = Simulation::new(1, 0);
+    simulation.spawn_at(0, at(ARENA_WIDTH as i32 - 2, 5), Direction::Right);
+    drop_item(
+      &mut simulation,
+      at(ARENA_WIDTH as i32 - 1, 5),
+      PowerUp::Wrap,
+    );
+    assert!(simulation.step(&[]).is_empty());
+    assert!(simulation.step(&[]).is_empty());
+    assert_eq!(simulation.snake(0).unwrap().head, at(0, 5));
+  }
+
+  #[test]
+  fn effects_wear_off() {
+    let mut simulation = Simulation::new(1, 0);
+    simulation.spawn_at(0, at(10, 10), Direction::Up);
+    drop_item(&mut simulation, at(10, 11), PowerUp::Ghost);
+    for _ in 1..EFFECT_TICKS {
+      simulation.step(&[]);
+    }
+    assert!(simulation.snake(0).unwrap().has(PowerUp::Ghost));
+    simulation.step(&[]);
+    assert!(!simulation.snake(0).unwrap().has(PowerUp::Ghost));
+  }
+
+  /// A level whose obstacles cover every cell but a few in the bottom row
+  fn walled_in(free: std::ops::Range<i32>) -> Level {
+    let row = |x: i32, width: i32| Obstacle {
+      x,
+      y: 0,
+      width,
+      height: 1,
+    };
+    Level {
+      obstacles: vec![
+        Obstacle {
+          x: 0,
+          y: 1,
+          width: ARENA_WIDTH as i32,
+          height: ARENA_HEIGHT as i32 - 1,
+        },
+        row(0, free.start),
+        row(free.end, ARENA_WIDTH as i32 - free.end),
+      ],
+      ..Level::default()
+    }
+  }
+
+  #[test]
+  fn planned_spawns_are_spread_and_clear() {
+    let mut simulation = Simulation::new(8, 3);
+    assert!(simulation.spawn_all(&[0, 1, 2, 3, 4, 5, 6, 7]).is_empty());
+    let heads: Vec<Position> = (0..8)
+      .map(|slot| simulation.snake(slot).unwrap().head)
+      .collect();
+    for (index, head) in heads.iter().enumerate() {
+      assert!(head.in_arena());
+      for other in &heads[index + 1..] {
+        assert!((head.x - other.x).abs() + (head.y - other.y).abs() >= SPAWN_DISTANCE);
+      }
+    }
+    // Nobody dies before getting a chance to turn
+    for _ in 0..SPAWN_RUNWAY {
+      assert!(simulation.step(&[]).is_empty());
+    }
+  }
+
+  #[test]
+  fn spawning_keeps_living_snakes() {
+    let mut simulation = Simulation::new(2, 0);
+    simulation.spawn_at(0, at(10, 10), Direction::Right);
+    simulation.spawn_all(&[0, 1]);
+    assert_eq!(simulation.snake(0).unwrap().head, at(10, 10));
+    assert!(simulation.is_alive(1));
+  }
+
+  #[test]
+  fn level_spawn_points_are_used() {
+    let mut simulation = Simulation::new(2, 0);
+    simulation.set_level(Level {
+      spawns: vec![SpawnPoint {
+        position: at(30, 40),
+        direction: Direction::Down,
+      }],
+      ..Level::default()
+    });
+    assert!(simulation.spawn_all(&[0, 1]).is_empty());
+    let snake = simulation.snake(0).unwrap();
+    assert_eq!((snake.head, snake.direction), (at(30, 40), Direction::Down));
+    assert!(simulation.is_alive(1));
+  }
+
+  #[test]
+  fn blocked_level_spawn_points_are_replaced() {
+    let mut simulation = Simulation::new(2, 0);
+    let spawn = SpawnPoint {
+      position: at(5, 5),
+      direction: Direction::Right,
+    };
+    simulation.set_level(Level {
+      obstacles: vec![Obstacle {
+        x: 0,
+        y: 0,
+        width: 10,
+        height: 10,
+      }],
+      spawns: vec![spawn, spawn],
+      ..Level::default()
+    });
+    assert_eq!(simulation.spawn_all(&[0, 1]), [0, 1]);
+    for slot in 0..2 {
+      assert!(simulation
+        .occupancy()
+        .is_free(simulation.snake(slot).unwrap().head));
+    }
+
+    // Two slots sharing a spawn point
+    let mut simulation = Simulation::new(2, 0);
+    simulation.set_level(Level {
+      spawns: vec![spawn, spawn],
+      ..Level::default()
+    });
+    assert_eq!(simulation.spawn_all(&[0, 1]), [1]);
+    assert_ne!(simulation.snake(1).unwrap().head, at(5, 5));
+  }
+
+  #[test]
+  fn a_crowded_arena_still_spawns_on_free_cells() {
+    let mut simulation = Simulation::new(3, 0);
+    simulation.set_level(walled_in(100..103));
+    simulation.spawn_all(&[0, 1, 2]);
+    let mut heads: Vec<i32> = (0..3)
+      .map(|slot| {
+        let head = simulation.snake(slot).unwrap().head;
+        assert_eq!(head.y, 0);
+        head.x
+      })
+      .collect();
+    heads.sort();
+    assert_eq!(heads, [100, 101, 102]);
+  }
+
+  #[test]
+  fn pickups_are_skipped_when_no_cell_is_free() {
+    let mut simulation = Simulation::new(1, 0);
+    simulation.set_level(walled_in(100..101));
+    simulation.set_trail_mode(TrailMode::Food);
+    simulation.set_power_ups(true);
+    simulation.spawn_at(0, at(100, 0), Direction::Up);
+    simulation.step(&[]);
+    assert!(simulation.food().is_empty());
+    assert!(simulation.items().is_empty());
+  }
+
+  #[test]
+  fn same_seed_replays_the_same_round() {
+    let play = |seed| {
+      let mut simulation = Simulation::new(3, seed);
+      simulation.set_power_ups(true);
+      simulation.spawn_all(&[0, 1, 2]);
+      let mut eliminations = Vec::new();
+      for _ in 0..500 {
+        eliminations.extend(simulation.step(&[]));
+      }
+      (eliminations, simulation.items().to_vec())
+    };
+    assert_eq!(play(7), play(7));
+  }
+}
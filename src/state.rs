@@ -1,31 +1,117 @@
 use bevy::prelude::*;
 
+use crate::{roster, scoring, sim};
+
 #[derive(Debug, Default, Clone, Eq, PartialEq, Hash, States)]
 pub enum AppState {
   #[default]
   Loading,
   Splash,
   MainMenu,
+  Controls,
+  Editor,
+  Lobby,
   Game,
   Paused,
   GameOver,
 }
 
+/// Transitions that begin a new match: from the lobby, a test of the level being
+/// edited, or a rematch from the standings. Pausing and resuming leave and re-enter
+/// `Game` without starting over.
+pub const MATCH_START: [OnTransition<AppState>; 3] = [
+  OnTransition {
+    from: AppState::Lobby,
+    to: AppState::Game,
+  },
+  OnTransition {
+    from: AppState::Editor,
+    to: AppState::Game,
+  },
+  OnTransition {
+    from: AppState::GameOver,
+    to: AppState::Game,
+  },
+];
+
+/// States that end the match in progress; its entities are cleaned up on entering them
+pub const MATCH_END: [AppState; 2] = [AppState::MainMenu, AppState::GameOver];
+
+/// Progress of the current match. Who plays and how they are doing lives in the
+/// `roster::Roster`; whether their snake is still alive is up to the simulation.
 #[derive(Resource, Default)]
 pub struct InGameState {
-  //current_round: usize,
-  //total_players: usize,
-  //winning_player: Option<String>,
-  pub player1: bool,
-  pub player2: bool,
-  pub player3: bool,
-  pub player4: bool,
+  pub current_round: usize,
+  pub winning_player: Option<String>,
 }
 
-#[derive(Resource, Default)]
-pub struct PlayerScores {
-  pub player1: i32,
-  pub player2: i32,
-  pub player3: i32,
-  pub player4: i32,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchFormat {
+  /// The match ends as soon as someone reaches this many points
+  FirstToPoints(i32),
+  /// At most this many rounds; ends early once the leader can no longer be caught
+  BestOfRounds(usize),
+}
+
+impl MatchFormat {
+  /// Formats offered in the lobby, in the order the button cycles through them
+  pub const CHOICES: [MatchFormat; 5] = [
+    MatchFormat::BestOfRounds(3),
+    MatchFormat::BestOfRounds(5),
+    MatchFormat::BestOfRounds(7),
+    MatchFormat::FirstToPoints(5),
+    MatchFormat::FirstToPoints(10),
+  ];
+
+  pub fn label(self) -> String {
+    match self {
+      MatchFormat::FirstToPoints(points) => format!("First to {} points", points),
+      MatchFormat::BestOfRounds(rounds) => format!("Best of {} rounds", rounds),
+    }
+  }
+
+  pub fn next(self) -> Self {
+    let index = Self::CHOICES
+      .iter()
+      .position(|format| *format == self)
+      .unwrap_or(0);
+    Self::CHOICES[(index + 1) % Self::CHOICES.len()]
+  }
+
+  pub fn is_decided(self, in_game_state: &InGameState, roster: &roster::Roster) -> bool {
+    match self {
+      MatchFormat::FirstToPoints(points) => roster.joined().any(|(_, slot)| slot.score >= points),
+      MatchFormat::BestOfRounds(rounds) => {
+        in_game_state.current_round >= rounds
+          || roster
+            .joined()
+            .any(|(_, slot)| slot.round_wins > rounds / 2)
+      }
+    }
+  }
+}
+
+/// Match options picked in the lobby before the first round
+#[derive(Resource)]
+pub struct MatchSettings {
+  pub format: MatchFormat,
+  pub scoring: scoring::ScoringScheme,
+  pub trail_mode: sim::TrailMode,
+  pub power_ups: bool,
+  pub wrap_around: bool,
+  /// Index into `levels::Levels`
+  pub level: usize,
+}
+
+impl Default for MatchSettings {
+  fn default() -> Self {
+    MatchSettings {
+      format: MatchFormat::BestOfRounds(5),
+      scoring: scoring::ScoringScheme::default(),
+      trail_mode: sim::TrailMode::default(),
+      power_ups: false,
+      wrap_around: false,
+      level: 0,
+    }
+  }
 }
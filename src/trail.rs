@@ -0,0 +1,245 @@
+use bevy::{
+  prelude::*,
+  render::{
+    render_resource::{Extent3d, TextureDimension, TextureFormat},
+    texture::ImageSampler,
+  },
+};
+
+use crate::{game, player, roster, sim, state};
+
+pub struct TrailPlugin;
+
+/// Trails are drawn this many pixels wide around the cell they cover
+const BRUSH: i32 = 3;
+const OBSTACLE_COLOR: [u8; 4] = [90, 90, 100, 255];
+
+/// All trails are painted into one arena-sized image shown on a single sprite,
+/// one texel per cell. Only cells that changed since the last frame are painted; the
+/// image is redrawn from the simulation, obstacles first, when the occupancy no longer
+/// remembers them.
+#[derive(Resource, Default)]
+struct TrailCanvas {
+  image: Handle<Image>,
+  /// The `sim::Occupancy::mark` the image shows, `None` before the first drawing
+  painted: Option<u64>,
+}
+
+/// A pickup lying in the arena, the nth in `sim::Simulation::food`
+#[derive(Component)]
+struct Pickup(usize);
+
+/// The sprite showing the `TrailCanvas` image
+#[derive(Component)]
+struct TrailSprite;
+
+/// This plugin draws every player's trail from the simulation. The ECS only holds a
+/// single sprite; which cell belongs to whom lives in the simulation's occupancy.
+impl Plugin for TrailPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .add_state::<state::AppState>()
+      .init_resource::<TrailCanvas>()
+      .add_systems(
+        Update,
+        (fit_canvas, paint_trails, sync_pickups).run_if(in_state(state::AppState::Game)),
+      );
+    for transition in state::MATCH_START {
+      app.add_systems(transition, canvas_setup);
+    }
+    for match_end in state::MATCH_END {
+      app.add_systems(OnEnter(match_end), despawn_canvas);
+    }
+  }
+}
+
+fn canvas_setup(
+  mut commands: Commands,
+  mut images: ResMut<Assets<Image>>,
+  mut canvas: ResMut<TrailCanvas>,
+) {
+  let mut image = Image::new_fill(
+    Extent3d {
+      width: game::ARENA_WIDTH,
+      height: game::ARENA_HEIGHT,
+      depth_or_array_layers: 1,
+    },
+    TextureDimension::D2,
+    &[0, 0, 0, 0],
+    TextureFormat::Rgba8UnormSrgb,
+  );
+  image.sampler_descriptor = ImageSampler::nearest();
+  *canvas = TrailCanvas {
+    image: images.add(image),
+    ..default()
+  };
+  commands.spawn((
+    SpriteBundle {
+      texture: canvas.image.clone(),
+      ..default()
+    },
+    TrailSprite,
+  ));
+}
+
+fn despawn_canvas(
+  mut commands: Commands,
+  sprites: Query<Entity, Or<(With<TrailSprite>, With<Pickup>)>>,
+  mut images: ResMut<Assets<Image>>,
+  mut canvas: ResMut<TrailCanvas>,
+) {
+  for entity in sprites.iter() {
+    commands.entity(entity).despawn();
+  }
+  images.remove(&canvas.image);
+  *canvas = TrailCanvas::default();
+}
+
+/// Stretches the canvas over the window, the same area `position_translation` maps
+/// the arena to
+fn fit_canvas(windows: Query<&Window>, mut sprites: Query<&mut Sprite, With<TrailSprite>>) {
+  let window = windows.single();
+  let size = Some(Vec2::new(window.width(), window.height()));
+  for mut sprite in sprites.iter_mut() {
+    if sprite.custom_size != size {
+      sprite.custom_size = size;
+    }
+  }
+}
+
+fn paint_trails(
+  simulation: Res<player::Simulation>,
+  roster: Res<roster::Roster>,
+  mut canvas: ResMut<TrailCanvas>,
+  mut images: ResMut<Assets<Image>>,
+) {
+  let occupancy = simulation.occupancy();
+  let mark = occupancy.mark();
+  if canvas.painted == Some(mark) {
+    return;
+  }
+  let canvas = canvas.as_mut();
+  let Some(image) = images.get_mut(&canvas.image) else {
+    return;
+  };
+  let colors: Vec<[u8; 4]> = roster
+    .slots
+    .iter()
+    .map(|slot| slot.color.as_rgba_u8())
+    .collect();
+  let wrap_around = simulation.wrap_around();
+  match canvas
+    .painted
+    .and_then(|painted| occupancy.changed_since(painted))
+  {
+    Some(changed) => {
+      for position in changed {
+        match occupancy.get(position) {
+          Some(owner) => paint_cell(image, position, colors[owner], wrap_around),
+          None => erase_cell(image, position, occupancy, &colors, wrap_around),
+        }
+      }
+    }
+    None => {
+      image.data.fill(0);
+      let obstacles = simulation.level().obstacles.iter();
+      for position in obstacles.flat_map(|obstacle| obstacle.cells()) {
+        paint_texel(image, position, OBSTACLE_COLOR);
+      }
+      for slot in simulation.alive() {
+        for position in &simulation.snake(slot).unwrap().trail {
+          paint_cell(image, *position, colors[slot], wrap_around);
+        }
+      }
+    }
+  }
+  canvas.painted = Some(mark);
+}
+
+/// Keeps one small sprite on every pickup in the arena
+fn sync_pickups(
+  mut commands: Commands,
+  simulation: Res<player::Simulation>,
+  mut pickups: Query<(Entity, &Pickup, &mut game::Position)>,
+) {
+  let food = simulation.food();
+  for (entity, pickup, mut position) in pickups.iter_mut() {
+    match food.get(pickup.0) {
+      Some(food) if position.0 != *food => position.0 = *food,
+      Some(_) => {}
+      None => commands.entity(entity).despawn(),
+    }
+  }
+  for (index, food) in food.iter().enumerate().skip(pickups.iter().len()) {
+    commands.spawn((
+      SpriteBundle {
+        sprite: Sprite {
+          color: Color::WHITE,
+          custom_size: Some(Vec2::new(5.0, 5.0)),
+          ..default()
+        },
+        transform: Transform::from_translation(Vec3::new(0., 0., 0.5)),
+        ..default()
+      },
+      game::Position(*food),
+      Pickup(index),
+    ));
+  }
+}
+
+/// The texels the brush covers around `position`. With `wrap_around` the brush
+/// continues on the opposite edge, so trails look the same on both sides of the seam.
+fn brush(position: sim::Position, wrap_around: bool) -> impl Iterator<Item = sim::Position> {
+  let reach = BRUSH / 2;
+  (-reach..=reach).flat_map(move |dy| {
+    (-reach..=reach).map(move |dx| {
+      let cell = sim::Position {
+        x: position.x + dx,
+        y: position.y + dy,
+      };
+      if wrap_around {
+        cell.wrapped()
+      } else {
+        cell
+      }
+    })
+  })
+}
+
+/// Fills the brush around `position`. Image rows run top to bottom while arena y
+/// runs bottom to top.
+fn paint_cell(image: &mut Image, position: sim::Position, color: [u8; 4], wrap_around: bool) {
+  for texel in brush(position, wrap_around) {
+    paint_texel(image, texel, color);
+  }
+}
+
+/// Repaints the brush around `position` once nothing covers its cell any more. Every
+/// texel keeps the colour of an obstacle on it or a trail cell whose brush still
+/// reaches it.
+fn erase_cell(
+  image: &mut Image,
+  position: sim::Position,
+  occupancy: &sim::Occupancy,
+  colors: &[[u8; 4]],
+  wrap_around: bool,
+) {
+  for texel in brush(position, wrap_around) {
+    let color = match occupancy.is_obstacle(texel) {
+      true => OBSTACLE_COLOR,
+      false => brush(texel, wrap_around)
+        .find_map(|cell| occupancy.get(cell))
+        .map_or([0; 4], |owner| colors[owner]),
+    };
+    paint_texel(image, texel, color);
+  }
+}
+
+fn paint_texel(image: &mut Image, cell: sim::Position, color: [u8; 4]) {
+  if !cell.in_arena() {
+    return;
+  }
+  let row = game::ARENA_HEIGHT as usize - 1 - cell.y as usize;
+  let index = (row * game::ARENA_WIDTH as usize + cell.x as usize) * 4;
+  image.data[index..index + 4].copy_from_slice(&color);
+}